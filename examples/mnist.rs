@@ -23,7 +23,7 @@ fn main() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn create_image(img: &Vec<u8>) -> DynamicImage {
+fn create_image(img: &[u8]) -> DynamicImage {
     let mut image = DynamicImage::new_luma8(28, 28);
 
     for i in 0..28 {
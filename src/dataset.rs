@@ -1,22 +1,102 @@
 use std::fmt;
+use std::hash::Hash;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::vec;
 
-use rand::{Rng, SeedableRng};
+use rand::{Error, Rng, RngCore, SeedableRng};
 use rand_xorshift::XorShiftRng;
 
+mod bucket;
+mod cache;
+mod cardinality;
+mod checkpoint;
+mod group;
+mod indexed;
+mod interleave;
+mod map;
+mod padded_batch;
+mod par_map;
+mod prefetch;
+mod repeat;
+mod resample;
+mod sample;
+mod scan;
+mod shard;
+mod shuffle_all;
+mod split;
+mod unbatch;
+mod window;
+
+pub use self::bucket::BucketBySequenceLength;
+pub use self::cache::{Cache, CacheTo, Cacheable};
+pub use self::cardinality::Cardinality;
+pub(crate) use self::checkpoint::invalid_state;
+pub use self::checkpoint::Checkpoint;
+pub use self::group::{GroupByReducer, GroupByWindow};
+pub use self::indexed::IndexedDataset;
+pub use self::interleave::Interleave;
+pub use self::map::{Filter, Map};
+pub use self::padded_batch::PaddedBatch;
+pub use self::par_map::ParMap;
+pub use self::prefetch::Prefetch;
+pub use self::repeat::{Reiterable, Repeat};
+pub use self::resample::RejectionResample;
+pub use self::sample::{choose_from_datasets, sample_from_datasets};
+pub use self::scan::StatefulScan;
+pub use self::shard::Shard;
+pub use self::shuffle_all::ShuffleAll;
+pub use self::split::{KFold, Split};
+pub use self::unbatch::Unbatch;
+pub use self::window::Window;
+
 /// A Dataset is basically an iterator, with some additional capabilities.
 ///
-/// - `shuffle(buffer_size, seed)`: eagerly takes buffer_size items and returns shuffled
+/// - `shuffle(buffer_size, seed)`: takes buffer_size items on the first call to `next` and returns shuffled
+/// - `shuffle_all(seed)`: returns all items of a finite dataset in a uniformly random order
 /// - `batch(batch_size, drop_remainder)`: an array of batch_size at a time instead of 1 at a time
+/// - `padded_batch(batch_size, padding_value, drop_remainder)`: batch sequences, making each batch uniform by filling with `padding_value`
+/// - `unbatch()`/`rebatch(batch_size, drop_remainder)`: split batches back into elements, or into batches of a different size
+/// - `window(size, shift, stride, drop_remainder)`: sliding or tumbling windows, described at https://github.com/tensorflow/community/blob/master/rfcs/20180726-tf-data-windowing-reducers.md
+/// - `repeat(epochs)`/`repeat_forever()`: iterate a `Reiterable` dataset multiple times, `enumerate_epochs()` on the result returns the epoch and step of each element
+/// - `stateful_scan(initial_state, f)`: map elements with a state that is reset every epoch and saved in checkpoints
+/// - `prefetch(buffer_size)`: produce elements on a background thread, ahead of the consumer
+/// - `par_map(num_workers, f)`: map elements on a pool of worker threads
+/// - `cache()`/`cache_to(path)`: store the elements of the first full pass in memory or on disk
+/// - `shard(num_shards, index)`: every num_shards-th element, for distributed training
+/// - `interleave(cycle_length, block_length)`: interleave the elements of a dataset of datasets
+/// - `bucket_by_sequence_length(length_fn, bucket_boundaries, bucket_batch_sizes, padding_value, drop_remainder)`: padded batches of sequences with similar lengths
+/// - `group_by_window(key_fn, window_size)`: windows of elements with the same key
+/// - `group_by_reducer(key_fn, init, reduce, finalize)`: reduce the elements with the same key to a single value
+/// - `rejection_resample(class_fn, target_dist, initial_dist, seed)`: drop elements to reach a class distribution
+/// - `split(fraction, seed)`, `split_count(count, seed)`, `split_stratified(fraction, seed, label_fn)`: deterministic train/validation splits
+/// - `kfold(k, seed)`, `stratified_kfold(k, seed, label_fn)`: train/validation pairs for k-fold cross validation
 ///
-/// TODO:
+/// Datasets that support random access also implement `IndexedDataset`.
 ///
-/// - `padded_batch(batch_size, padding_value)`: make the dataset uniform by filling with `padding_value`.
-/// - `window`?: described at https://github.com/tensorflow/community/blob/master/rfcs/20180726-tf-data-windowing-reducers.md
+/// `cardinality()` returns the number of elements left, when it is known without iterating.
+///
+/// `sample_from_datasets` and `choose_from_datasets` combine multiple datasets into one.
 ///
 /// The goal is for this interface to be at feature parity with `tensorflow.data.Dataset`.
 pub trait Dataset: Iterator {
+    /// cardinality
+    ///
+    /// ```
+    /// use datasets::{Cardinality, Dataset};
+    ///
+    /// assert_eq!((0..10).batch(3, false).cardinality(), Cardinality::Known(4));
+    /// assert_eq!((0..10).repeat_forever().cardinality(), Cardinality::Infinite);
+    /// assert_eq!((0..10).filter(|x| x % 2 == 0).cardinality(), Cardinality::Unknown);
+    /// ```
+    fn cardinality(&self) -> Cardinality {
+        Cardinality::from_size_hint(self.size_hint())
+    }
+
     /// shuffle
-    /// TODO: handle error when batch_size is 0
+    ///
+    /// This will panic if buffer_size is 0.
     fn shuffle(self, buffer_size: usize, seed: u64) -> Shuffle<Self>
     where
         Self: Sized,
@@ -24,6 +104,29 @@ pub trait Dataset: Iterator {
         Shuffle::new(self, buffer_size, seed)
     }
 
+    /// shuffle_all
+    ///
+    /// This collects the entire dataset. For an `IndexedDataset`, like the mnist datasets,
+    /// `IndexedDataset::permuted` returns the same uniformly random order without collecting,
+    /// reading every element with `get` when it is returned.
+    ///
+    /// ```
+    /// use datasets::{Dataset, IndexedDataset};
+    ///
+    /// let mut collected: Vec<usize> = (0..8).shuffle_all(0).collect();
+    /// let mut lazy: Vec<usize> = (0..8).collect::<Vec<_>>().into_iter().permuted(0).collect();
+    ///
+    /// collected.sort();
+    /// lazy.sort();
+    /// assert_eq!(collected, lazy);
+    /// ```
+    fn shuffle_all(self, seed: u64) -> ShuffleAll<Self::Item>
+    where
+        Self: Sized,
+    {
+        ShuffleAll::new(self.collect(), seed)
+    }
+
     /// batch
     /// TODO: handle error when batch_size is 0
     fn batch(self, batch_size: usize, drop_remainder: bool) -> Batch<Self>
@@ -36,6 +139,382 @@ pub trait Dataset: Iterator {
             drop_remainder,
         }
     }
+
+    /// padded_batch
+    /// TODO: handle error when batch_size is 0
+    fn padded_batch<T>(
+        self,
+        batch_size: usize,
+        padding_value: T,
+        drop_remainder: bool,
+    ) -> PaddedBatch<Self, T>
+    where
+        Self: Sized + Iterator<Item = Vec<T>>,
+        T: Clone,
+    {
+        PaddedBatch::new(self, batch_size, padding_value, drop_remainder)
+    }
+
+    /// unbatch
+    fn unbatch<T>(self) -> Unbatch<Self, T>
+    where
+        Self: Sized + Iterator<Item = Vec<T>>,
+    {
+        Unbatch::new(self)
+    }
+
+    /// rebatch
+    /// TODO: handle error when batch_size is 0
+    fn rebatch<T>(self, batch_size: usize, drop_remainder: bool) -> Batch<Unbatch<Self, T>>
+    where
+        Self: Sized + Iterator<Item = Vec<T>>,
+    {
+        self.unbatch().batch(batch_size, drop_remainder)
+    }
+
+    /// window
+    ///
+    /// This will panic if any of size, shift or stride is 0.
+    fn window(self, size: usize, shift: usize, stride: usize, drop_remainder: bool) -> Window<Self>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+    {
+        Window::new(self, size, shift, stride, drop_remainder)
+    }
+
+    /// repeat
+    fn repeat(self, epochs: usize) -> Repeat<Self>
+    where
+        Self: Reiterable,
+    {
+        Repeat::new(self, Some(epochs))
+    }
+
+    /// repeat_forever
+    fn repeat_forever(self) -> Repeat<Self>
+    where
+        Self: Reiterable,
+    {
+        Repeat::new(self, None)
+    }
+
+    /// into_stream returns a `futures::Stream` of the elements of this dataset, produced on a
+    /// background thread like `prefetch`.
+    #[cfg(feature = "stream")]
+    fn into_stream(self, buffer_size: usize) -> crate::stream::IntoStream<Self>
+    where
+        Self: Sized + Send + 'static,
+        Self::Item: Send + 'static,
+    {
+        crate::stream::IntoStream::new(self, buffer_size)
+    }
+
+    /// stateful_scan
+    fn stateful_scan<S, B, F>(self, initial_state: S, f: F) -> StatefulScan<Self, S, F>
+    where
+        Self: Sized,
+        S: Clone,
+        F: FnMut(&mut S, Self::Item) -> B,
+    {
+        StatefulScan::new(self, initial_state, f)
+    }
+
+    /// prefetch
+    fn prefetch(self, buffer_size: usize) -> Prefetch<Self>
+    where
+        Self: Sized + Send + 'static,
+        Self::Item: Send + 'static,
+    {
+        Prefetch::new(self, buffer_size)
+    }
+
+    /// par_map
+    ///
+    /// This will panic if num_workers is 0.
+    fn par_map<B, F>(self, num_workers: usize, f: F) -> ParMap<Self, B>
+    where
+        Self: Sized,
+        Self::Item: Send + 'static,
+        B: Send + 'static,
+        F: Fn(Self::Item) -> B + Send + Sync + 'static,
+    {
+        ParMap::new(self, num_workers, Arc::new(f))
+    }
+
+    /// checkpointable_map is `Iterator::map`, returning a dataset that is `Checkpoint` and
+    /// `Reiterable` when this one is.
+    fn checkpointable_map<B, F>(self, f: F) -> Map<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(Self::Item) -> B,
+    {
+        Map::new(self, f)
+    }
+
+    /// checkpointable_filter is `Iterator::filter`, returning a dataset that is `Checkpoint` and
+    /// `Reiterable` when this one is.
+    fn checkpointable_filter<P>(self, predicate: P) -> Filter<Self, P>
+    where
+        Self: Sized,
+        P: FnMut(&Self::Item) -> bool,
+    {
+        Filter::new(self, predicate)
+    }
+
+    /// cache
+    fn cache(self) -> Cache<Self>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+    {
+        Cache::new(self)
+    }
+
+    /// cache_to
+    fn cache_to(self, path: &Path) -> CacheTo<Self>
+    where
+        Self: Sized,
+        Self::Item: Cacheable,
+    {
+        CacheTo::new(self, path)
+    }
+
+    /// shard
+    ///
+    /// This will panic if num_shards is 0 or index is not less than num_shards.
+    fn shard(self, num_shards: usize, index: usize) -> Shard<Self>
+    where
+        Self: Sized,
+    {
+        Shard::new(self, num_shards, index)
+    }
+
+    /// interleave
+    ///
+    /// This will panic if cycle_length or block_length is 0.
+    fn interleave(self, cycle_length: usize, block_length: usize) -> Interleave<Self>
+    where
+        Self: Sized,
+        Self::Item: IntoIterator,
+    {
+        Interleave::new(self, cycle_length, block_length)
+    }
+
+    /// bucket_by_sequence_length
+    ///
+    /// This will panic if the boundaries are not increasing, there isn't exactly one more batch
+    /// size than boundaries, or a batch size is 0.
+    fn bucket_by_sequence_length<T, F>(
+        self,
+        length_fn: F,
+        bucket_boundaries: Vec<usize>,
+        bucket_batch_sizes: Vec<usize>,
+        padding_value: T,
+        drop_remainder: bool,
+    ) -> BucketBySequenceLength<Self, T, F>
+    where
+        Self: Sized + Iterator<Item = Vec<T>>,
+        T: Clone,
+        F: FnMut(&Vec<T>) -> usize,
+    {
+        BucketBySequenceLength::new(
+            self,
+            length_fn,
+            bucket_boundaries,
+            bucket_batch_sizes,
+            padding_value,
+            drop_remainder,
+        )
+    }
+
+    /// group_by_window
+    ///
+    /// This will panic if window_size is 0.
+    fn group_by_window<K, F>(self, key_fn: F, window_size: usize) -> GroupByWindow<Self, K, F>
+    where
+        Self: Sized,
+        K: Eq + Hash + Clone,
+        F: FnMut(&Self::Item) -> K,
+    {
+        GroupByWindow::new(self, key_fn, window_size)
+    }
+
+    /// group_by_reducer
+    fn group_by_reducer<K, S, B, F, N, R, Z>(
+        self,
+        key_fn: F,
+        init: N,
+        reduce: R,
+        finalize: Z,
+    ) -> GroupByReducer<Self, K, S, B, F, N, R, Z>
+    where
+        Self: Sized,
+        K: Eq + Hash + Clone,
+        F: FnMut(&Self::Item) -> K,
+        N: FnMut(&K) -> S,
+        R: FnMut(&mut S, Self::Item),
+        Z: FnMut(K, S) -> B,
+    {
+        GroupByReducer::new(self, key_fn, init, reduce, finalize)
+    }
+
+    /// rejection_resample
+    fn rejection_resample<F>(
+        self,
+        class_fn: F,
+        target_dist: Vec<f64>,
+        initial_dist: Option<Vec<f64>>,
+        seed: u64,
+    ) -> RejectionResample<Self, F>
+    where
+        Self: Sized,
+        F: FnMut(&Self::Item) -> usize,
+    {
+        RejectionResample::new(self, class_fn, target_dist, initial_dist, seed)
+    }
+
+    /// split randomly splits a dataset into two, with `fraction` of the elements in the first.
+    ///
+    /// This collects the entire dataset, and the result only depends on its elements and `seed`.
+    ///
+    /// ```
+    /// use datasets::Dataset;
+    ///
+    /// let (train, validation) = (0..100).split(0.9, 42);
+    /// assert_eq!(train.len(), 90);
+    /// assert_eq!(validation.len(), 10);
+    ///
+    /// let (again, _) = (0..100).split(0.9, 42);
+    /// assert!(train.eq(again));
+    /// ```
+    ///
+    /// This will panic if fraction is not between 0 and 1.
+    fn split(
+        self,
+        fraction: f64,
+        seed: u64,
+    ) -> (vec::IntoIter<Self::Item>, vec::IntoIter<Self::Item>)
+    where
+        Self: Sized,
+    {
+        let v: Vec<Self::Item> = self.collect();
+        let count = split::fraction_count(v.len(), fraction);
+        let (first, second) = split::split_vec(v, count, &mut XorShiftRng::seed_from_u64(seed));
+        (first.into_iter(), second.into_iter())
+    }
+
+    /// split_count randomly splits a dataset into two, with `count` elements in the first, or
+    /// all of them if there are fewer.
+    ///
+    /// ```
+    /// use datasets::Dataset;
+    ///
+    /// let (train, validation) = (0..100).split_count(80, 42);
+    /// assert_eq!(train.len(), 80);
+    /// assert_eq!(validation.len(), 20);
+    /// ```
+    fn split_count(
+        self,
+        count: usize,
+        seed: u64,
+    ) -> (vec::IntoIter<Self::Item>, vec::IntoIter<Self::Item>)
+    where
+        Self: Sized,
+    {
+        let v: Vec<Self::Item> = self.collect();
+        let count = count.min(v.len());
+        let (first, second) = split::split_vec(v, count, &mut XorShiftRng::seed_from_u64(seed));
+        (first.into_iter(), second.into_iter())
+    }
+
+    /// split_stratified randomly splits a dataset into two, with `fraction` of the elements of
+    /// every label in the first.
+    ///
+    /// ```
+    /// use datasets::Dataset;
+    ///
+    /// let data = (0..100).map(|x| (x, if x < 20 { 1 } else { 0 }));
+    /// let (train, validation) = data.split_stratified(0.5, 42, |(_, label)| *label);
+    ///
+    /// assert_eq!(train.filter(|(_, label)| *label == 1).count(), 10);
+    /// assert_eq!(validation.filter(|(_, label)| *label == 1).count(), 10);
+    /// ```
+    ///
+    /// This will panic if fraction is not between 0 and 1.
+    fn split_stratified<K, F>(
+        self,
+        fraction: f64,
+        seed: u64,
+        label_fn: F,
+    ) -> (vec::IntoIter<Self::Item>, vec::IntoIter<Self::Item>)
+    where
+        Self: Sized,
+        K: Eq + Hash,
+        F: FnMut(&Self::Item) -> K,
+    {
+        let (first, second) = split::stratified_split_vec(
+            self.collect(),
+            fraction,
+            label_fn,
+            &mut XorShiftRng::seed_from_u64(seed),
+        );
+        (first.into_iter(), second.into_iter())
+    }
+
+    /// kfold returns the `k` folds of k-fold cross validation, each a pair of train and
+    /// validation datasets.
+    ///
+    /// This collects the entire dataset, and the result only depends on its elements and `seed`.
+    ///
+    /// ```
+    /// use datasets::Dataset;
+    ///
+    /// let folds: Vec<_> = (0..10).kfold(5, 42).collect();
+    /// assert_eq!(folds.len(), 5);
+    ///
+    /// let mut validation: Vec<usize> = Vec::new();
+    /// for (train, v) in folds {
+    ///     assert_eq!(train.len(), 8);
+    ///     validation.extend(v);
+    /// }
+    ///
+    /// validation.sort();
+    /// assert_eq!(validation, (0..10).collect::<Vec<usize>>());
+    /// ```
+    ///
+    /// This will panic if k is less than 2, or greater than the number of elements.
+    fn kfold(self, k: usize, seed: u64) -> KFold<Self::Item>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+    {
+        KFold::new(self.collect(), k, seed)
+    }
+
+    /// stratified_kfold is `kfold`, with the elements of every label spread evenly across the
+    /// folds.
+    ///
+    /// ```
+    /// use datasets::Dataset;
+    ///
+    /// let data = (0..20).map(|x| (x, if x < 5 { 1 } else { 0 }));
+    ///
+    /// for (_, validation) in data.stratified_kfold(5, 42, |(_, label)| *label) {
+    ///     assert_eq!(validation.filter(|(_, label)| *label == 1).count(), 1);
+    /// }
+    /// ```
+    ///
+    /// This will panic if k is less than 2, or greater than the number of elements.
+    fn stratified_kfold<K, F>(self, k: usize, seed: u64, label_fn: F) -> KFold<Self::Item>
+    where
+        Self: Sized,
+        Self::Item: Clone,
+        K: Eq + Hash,
+        F: FnMut(&Self::Item) -> K,
+    {
+        KFold::new_stratified(self.collect(), k, seed, label_fn)
+    }
 }
 
 // TODO: reconsider this, do we want all iterators be datasets.
@@ -51,42 +530,103 @@ impl<I> Dataset for I where I: Iterator {}
 /// assert_eq!(v, vec![4, 2, 0, 3, 7, 6, 5, 1]);
 /// ```
 ///
-/// TODO: implement `reshuffle_each_iteration` as defined at https://www.tensorflow.org/api_docs/python/tf/data/Dataset#shuffle.
+/// When repeated, every epoch is shuffled with a different seed derived from `seed`, as with
+/// `reshuffle_each_iteration` at https://www.tensorflow.org/api_docs/python/tf/data/Dataset#shuffle.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<usize> = (0..4).shuffle(4, 0).repeat(2).collect();
+/// assert_eq!(v, vec![0, 1, 3, 2, 1, 0, 3, 2]);
+///
+/// let v: Vec<usize> = (0..4)
+///     .shuffle(4, 0)
+///     .reshuffle_each_iteration(false)
+///     .repeat(2)
+///     .collect();
+/// assert_eq!(v, vec![0, 1, 3, 2, 0, 1, 3, 2]);
+/// ```
+///
+/// The order is only uniformly random when `buffer_size` is at least the number of elements,
+/// `shuffle_all` always returns a uniformly random permutation.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// // a buffer larger than the dataset
+/// let mut v: Vec<usize> = (0..3).shuffle(8, 0).collect();
+/// v.sort();
+/// assert_eq!(v, vec![0, 1, 2]);
+/// ```
 pub struct Shuffle<I>
 where
     I: Iterator,
 {
     iter: I,
     buffer_size: usize,
-    buffer: Vec<Option<<I as Iterator>::Item>>,
-    rng: XorShiftRng,
+    buffer: Vec<<I as Iterator>::Item>,
+    filled: bool,
+    seed: u64,
+    reshuffle_each_iteration: bool,
+    rng: CountingRng,
 }
 
 impl<I> Shuffle<I>
 where
     I: Iterator,
 {
-    fn new(mut iter: I, buffer_size: usize, seed: u64) -> Shuffle<I> {
-        // NOTE: cannot do vec! here because Option<<I as Iterator>::Item> does not implement Clone
-        let mut buffer = Vec::with_capacity(buffer_size);
-
-        let mut i = 0;
-        while i < buffer_size {
-            let val = iter.next();
-            if val.is_none() {
-                break;
-            } else {
-                buffer.push(val);
-                i += 1;
-            }
-        }
+    fn new(iter: I, buffer_size: usize, seed: u64) -> Shuffle<I> {
+        assert!(
+            buffer_size > 0,
+            "shuffle: buffer_size must be greater than 0"
+        );
 
         Shuffle {
             iter,
             buffer_size,
-            buffer,
-            rng: XorShiftRng::seed_from_u64(seed),
+            buffer: Vec::with_capacity(buffer_size),
+            filled: false,
+            seed,
+            reshuffle_each_iteration: true,
+            rng: CountingRng::new(seed),
+        }
+    }
+
+    /// when set to false, every epoch of a repeated shuffle returns elements in the same order.
+    ///
+    /// defaults to true.
+    pub fn reshuffle_each_iteration(mut self, reshuffle_each_iteration: bool) -> Shuffle<I> {
+        self.reshuffle_each_iteration = reshuffle_each_iteration;
+        self
+    }
+
+    fn fill(&mut self) {
+        while self.buffer.len() < self.buffer_size {
+            match self.iter.next() {
+                Some(val) => self.buffer.push(val),
+                None => break,
+            }
         }
+
+        self.filled = true;
+    }
+}
+
+impl<I> Reiterable for Shuffle<I>
+where
+    I: Reiterable,
+{
+    fn reiterate(&self, epoch: usize) -> Shuffle<I> {
+        let seed = if self.reshuffle_each_iteration {
+            repeat::epoch_seed(self.seed, epoch)
+        } else {
+            self.seed
+        };
+
+        let mut shuffle = Shuffle::new(self.iter.reiterate(epoch), self.buffer_size, seed);
+        shuffle.seed = self.seed;
+        shuffle.reshuffle_each_iteration = self.reshuffle_each_iteration;
+        shuffle
     }
 }
 
@@ -103,6 +643,26 @@ where
     }
 }
 
+impl<I> Checkpoint for Shuffle<I>
+where
+    I: Iterator + Checkpoint,
+    <I as Iterator>::Item: Cacheable,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.filled.write_to(writer)?;
+        self.rng.write_to(writer)?;
+        self.buffer.write_to(writer)?;
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.filled = bool::read_from(reader)?;
+        self.rng = CountingRng::read_from(reader)?;
+        self.buffer = Vec::read_from(reader)?;
+        self.iter.restore_state(reader)
+    }
+}
+
 impl<I> Iterator for Shuffle<I>
 where
     I: Iterator,
@@ -110,23 +670,97 @@ where
     type Item = <I as Iterator>::Item;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.buffer_size == 0 {
-            None
+        if !self.filled {
+            self.fill();
+        }
+
+        if self.buffer.is_empty() {
+            return None;
+        }
+
+        // the returned element is replaced with the next one, or the last one in the buffer
+        // once the inner iterator is exhausted
+        let index = self.rng.gen_range(0, self.buffer.len());
+        match self.iter.next() {
+            Some(val) => Some(std::mem::replace(&mut self.buffer[index], val)),
+            None => Some(self.buffer.swap_remove(index)),
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.filled {
+            let buffered = self.buffer.len();
+            cardinality::add_hints(self.iter.size_hint(), (buffered, Some(buffered)))
         } else {
-            let index = self.rng.gen_range(0, self.buffer_size);
-            let val = self.buffer[index].take();
+            self.iter.size_hint()
+        }
+    }
+}
 
-            let replace_val = self.iter.next();
-            if replace_val.is_some() {
-                self.buffer[index] = replace_val;
-            } else {
-                self.buffer[index] = self.buffer[self.buffer_size - 1].take();
-                self.buffer_size -= 1;
-            }
+impl<I> ExactSizeIterator for Shuffle<I> where I: ExactSizeIterator {}
+
+/// XorShiftRng does not expose its state, so CountingRng counts the values drawn from it, to be
+/// able to recreate it from its seed when restoring a checkpoint.
+#[derive(Debug)]
+struct CountingRng {
+    rng: XorShiftRng,
+    seed: u64,
+    draws: u64,
+}
+
+impl CountingRng {
+    fn new(seed: u64) -> CountingRng {
+        CountingRng {
+            rng: XorShiftRng::seed_from_u64(seed),
+            seed,
+            draws: 0,
+        }
+    }
+
+    fn advance(&mut self, draws: u64) {
+        for _ in 0..draws {
+            self.next_u32();
+        }
+    }
+}
+
+impl Cacheable for CountingRng {
+    fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.seed.write_to(writer)?;
+        self.draws.write_to(writer)
+    }
+
+    fn read_from(reader: &mut dyn Read) -> io::Result<Self> {
+        let mut rng = CountingRng::new(u64::read_from(reader)?);
+        rng.advance(u64::read_from(reader)?);
+        Ok(rng)
+    }
+}
 
-            val
+impl RngCore for CountingRng {
+    fn next_u32(&mut self) -> u32 {
+        self.draws += 1;
+        self.rng.next_u32()
+    }
+
+    // NOTE: same as XorShiftRng, everything is drawn through next_u32 so it is counted.
+    fn next_u64(&mut self) -> u64 {
+        let x = u64::from(self.next_u32());
+        let y = u64::from(self.next_u32());
+        (y << 32) | x
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        for chunk in dest.chunks_mut(4) {
+            let bytes = self.next_u32().to_le_bytes();
+            chunk.copy_from_slice(&bytes[..chunk.len()]);
         }
     }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), Error> {
+        self.fill_bytes(dest);
+        Ok(())
+    }
 }
 
 /// Batch is an iterator that returns the contents of its inner iterator in batches
@@ -150,6 +784,32 @@ where
     drop_remainder: bool,
 }
 
+impl<I> Reiterable for Batch<I>
+where
+    I: Reiterable,
+{
+    fn reiterate(&self, epoch: usize) -> Batch<I> {
+        Batch {
+            iter: self.iter.reiterate(epoch),
+            batch_size: self.batch_size,
+            drop_remainder: self.drop_remainder,
+        }
+    }
+}
+
+impl<I> Checkpoint for Batch<I>
+where
+    I: Iterator + Checkpoint,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.iter.restore_state(reader)
+    }
+}
+
 impl<I> Iterator for Batch<I>
 where
     I: Iterator,
@@ -158,27 +818,43 @@ where
 
     fn next(&mut self) -> Option<Self::Item> {
         let mut i = 1;
-        let val = self.iter.next();
-        if val.is_none() {
-            None
-        } else {
-            let mut v = Vec::with_capacity(self.batch_size);
-            v.push(val.unwrap());
+        match self.iter.next() {
+            None => None,
+            Some(val) => {
+                let mut v = Vec::with_capacity(self.batch_size);
+                v.push(val);
 
-            while i < self.batch_size {
-                match self.iter.next() {
-                    Some(x) => v.push(x),
-                    None => break,
+                while i < self.batch_size {
+                    match self.iter.next() {
+                        Some(x) => v.push(x),
+                        None => break,
+                    }
+
+                    i += 1;
                 }
 
-                i += 1;
+                if v.len() < self.batch_size && self.drop_remainder {
+                    None
+                } else {
+                    Some(v)
+                }
             }
+        }
+    }
 
-            if v.len() < self.batch_size && self.drop_remainder {
-                None
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // NOTE: a batch_size of 0 returns batches of 1
+        let batch_size = self.batch_size.max(1);
+        let drop_remainder = self.drop_remainder;
+
+        cardinality::map_hint(self.iter.size_hint(), |n| {
+            if drop_remainder {
+                n / batch_size
             } else {
-                Some(v)
+                n.div_ceil(batch_size)
             }
-        }
+        })
     }
 }
+
+impl<I> ExactSizeIterator for Batch<I> where I: ExactSizeIterator {}
@@ -0,0 +1,223 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::dataset::checkpoint::invalid_state;
+use crate::dataset::padded_batch::pad;
+use crate::dataset::{Cacheable, Checkpoint, Reiterable};
+
+/// BucketBySequenceLength is an iterator that groups the sequences of its inner iterator into
+/// buckets by length, and returns padded batches from each bucket, like
+/// `tf.data.experimental.bucket_by_sequence_length`.
+///
+/// A sequence of length `l` goes into the first bucket `i` with `l < bucket_boundaries[i]`, or
+/// the last bucket if there is none. Bucket `i` returns batches of `bucket_batch_sizes[i]`, so
+/// there must be one more batch size than boundaries. Once the inner iterator is exhausted,
+/// the partially filled buckets are returned in order.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<Vec<Vec<usize>>> = vec![vec![1], vec![1, 2, 3], vec![1, 2], vec![1, 2, 3, 4]]
+///     .into_iter()
+///     .bucket_by_sequence_length(|s| s.len(), vec![3], vec![2, 2], 0, false)
+///     .collect();
+///
+/// assert_eq!(v.len(), 2);
+/// assert_eq!(v[0], vec![vec![1, 0], vec![1, 2]]);
+/// assert_eq!(v[1], vec![vec![1, 2, 3, 0], vec![1, 2, 3, 4]]);
+/// ```
+pub struct BucketBySequenceLength<I, T, F>
+where
+    I: Iterator<Item = Vec<T>>,
+{
+    iter: I,
+    length_fn: F,
+    bucket_boundaries: Vec<usize>,
+    bucket_batch_sizes: Vec<usize>,
+    padding_value: T,
+    drop_remainder: bool,
+    pad_to_bucket_boundary: bool,
+    buckets: Vec<Vec<Vec<T>>>,
+    flushed: usize,
+}
+
+impl<I, T, F> BucketBySequenceLength<I, T, F>
+where
+    I: Iterator<Item = Vec<T>>,
+    T: Clone,
+    F: FnMut(&Vec<T>) -> usize,
+{
+    pub(crate) fn new(
+        iter: I,
+        length_fn: F,
+        bucket_boundaries: Vec<usize>,
+        bucket_batch_sizes: Vec<usize>,
+        padding_value: T,
+        drop_remainder: bool,
+    ) -> BucketBySequenceLength<I, T, F> {
+        assert_eq!(
+            bucket_boundaries.len() + 1,
+            bucket_batch_sizes.len(),
+            "bucket_by_sequence_length: expected one more batch size than bucket boundaries"
+        );
+        assert!(
+            bucket_boundaries.windows(2).all(|w| w[0] < w[1]),
+            "bucket_by_sequence_length: bucket boundaries must be increasing"
+        );
+        assert!(
+            bucket_batch_sizes.iter().all(|s| *s > 0),
+            "bucket_by_sequence_length: batch sizes must be greater than 0"
+        );
+
+        BucketBySequenceLength {
+            iter,
+            length_fn,
+            buckets: bucket_batch_sizes
+                .iter()
+                .map(|s| Vec::with_capacity(*s))
+                .collect(),
+            bucket_boundaries,
+            bucket_batch_sizes,
+            padding_value,
+            drop_remainder,
+            pad_to_bucket_boundary: false,
+            flushed: 0,
+        }
+    }
+
+    /// pad the sequences in a bucket to its boundary minus 1 instead of the longest sequence in
+    /// each batch. The last bucket is still padded to its longest sequence.
+    ///
+    /// defaults to false.
+    pub fn pad_to_bucket_boundary(
+        mut self,
+        pad_to_bucket_boundary: bool,
+    ) -> BucketBySequenceLength<I, T, F> {
+        self.pad_to_bucket_boundary = pad_to_bucket_boundary;
+        self
+    }
+
+    fn padded(&self, bucket: usize, mut batch: Vec<Vec<T>>) -> Vec<Vec<T>> {
+        let length = if self.pad_to_bucket_boundary {
+            self.bucket_boundaries.get(bucket).map(|b| b - 1)
+        } else {
+            None
+        };
+
+        pad(&mut batch, &self.padding_value, length);
+        batch
+    }
+}
+
+impl<I, T, F> fmt::Debug for BucketBySequenceLength<I, T, F>
+where
+    I: Iterator<Item = Vec<T>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "BucketBySequenceLength {{ bucket_boundaries: {:?}, bucket_batch_sizes: {:?}, drop_remainder: {} }}",
+            self.bucket_boundaries, self.bucket_batch_sizes, self.drop_remainder
+        )
+    }
+}
+
+impl<I, T, F> Reiterable for BucketBySequenceLength<I, T, F>
+where
+    I: Reiterable + Iterator<Item = Vec<T>>,
+    T: Clone,
+    F: FnMut(&Vec<T>) -> usize + Clone,
+{
+    fn reiterate(&self, epoch: usize) -> BucketBySequenceLength<I, T, F> {
+        BucketBySequenceLength::new(
+            self.iter.reiterate(epoch),
+            self.length_fn.clone(),
+            self.bucket_boundaries.clone(),
+            self.bucket_batch_sizes.clone(),
+            self.padding_value.clone(),
+            self.drop_remainder,
+        )
+        .pad_to_bucket_boundary(self.pad_to_bucket_boundary)
+    }
+}
+
+impl<I, T, F> Checkpoint for BucketBySequenceLength<I, T, F>
+where
+    I: Iterator<Item = Vec<T>> + Checkpoint,
+    T: Cacheable,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.buckets.write_to(writer)?;
+        self.flushed.write_to(writer)?;
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let buckets: Vec<Vec<Vec<T>>> = Vec::read_from(reader)?;
+        if buckets.len() != self.bucket_batch_sizes.len() {
+            return Err(invalid_state("wrong number of buckets"));
+        }
+
+        self.buckets = buckets;
+        self.flushed = usize::read_from(reader)?;
+        self.iter.restore_state(reader)
+    }
+}
+
+impl<I, T, F> Iterator for BucketBySequenceLength<I, T, F>
+where
+    I: Iterator<Item = Vec<T>>,
+    T: Clone,
+    F: FnMut(&Vec<T>) -> usize,
+{
+    type Item = Vec<Vec<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.flushed == 0 {
+            while let Some(seq) = self.iter.next() {
+                let length = (self.length_fn)(&seq);
+                let bucket = self
+                    .bucket_boundaries
+                    .iter()
+                    .position(|b| length < *b)
+                    .unwrap_or(self.bucket_boundaries.len());
+
+                self.buckets[bucket].push(seq);
+
+                if self.buckets[bucket].len() == self.bucket_batch_sizes[bucket] {
+                    let batch = std::mem::replace(
+                        &mut self.buckets[bucket],
+                        Vec::with_capacity(self.bucket_batch_sizes[bucket]),
+                    );
+
+                    return Some(self.padded(bucket, batch));
+                }
+            }
+        }
+
+        // the inner iterator is exhausted, return the remaining partial batches
+        while self.flushed < self.buckets.len() {
+            let bucket = self.flushed;
+            self.flushed += 1;
+
+            let batch = std::mem::take(&mut self.buckets[bucket]);
+            if !batch.is_empty() && !self.drop_remainder {
+                return Some(self.padded(bucket, batch));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // the size of the buckets depends on the lengths of the sequences, so only the upper
+        // bound of one batch per sequence is known
+        let buffered: usize = self.buckets[self.flushed.min(self.buckets.len())..]
+            .iter()
+            .map(Vec::len)
+            .sum();
+
+        let (_, upper) = self.iter.size_hint();
+        (0, upper.and_then(|n| n.checked_add(buffered)))
+    }
+}
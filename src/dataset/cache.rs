@@ -0,0 +1,586 @@
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::dataset::checkpoint::invalid_state;
+use crate::dataset::{Checkpoint, Reiterable};
+
+/// Cacheable is implemented by types that can be written to and read back from a byte stream,
+/// which lets `cache_to` store them on disk.
+///
+/// Integers are stored in little endian, `usize` and `isize` as 64 bit values, and sequences
+/// are prefixed by their length.
+pub trait Cacheable: Sized {
+    /// write this value to `writer`.
+    fn write_to(&self, writer: &mut dyn Write) -> io::Result<()>;
+
+    /// read a value written by `write_to` from `reader`.
+    fn read_from(reader: &mut dyn Read) -> io::Result<Self>;
+}
+
+macro_rules! cacheable_number {
+    ($($t:ty),*) => {
+        $(
+            impl Cacheable for $t {
+                fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+                    writer.write_all(&self.to_le_bytes())
+                }
+
+                fn read_from(reader: &mut dyn Read) -> io::Result<Self> {
+                    let mut buf = [0; std::mem::size_of::<$t>()];
+                    reader.read_exact(&mut buf)?;
+                    Ok(<$t>::from_le_bytes(buf))
+                }
+            }
+        )*
+    };
+}
+
+cacheable_number!(u8, u16, u32, u64, i8, i16, i32, i64, f32, f64);
+
+impl Cacheable for usize {
+    fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        (*self as u64).write_to(writer)
+    }
+
+    fn read_from(reader: &mut dyn Read) -> io::Result<Self> {
+        Ok(u64::read_from(reader)? as usize)
+    }
+}
+
+impl Cacheable for isize {
+    fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        (*self as i64).write_to(writer)
+    }
+
+    fn read_from(reader: &mut dyn Read) -> io::Result<Self> {
+        Ok(i64::read_from(reader)? as isize)
+    }
+}
+
+impl Cacheable for bool {
+    fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        (*self as u8).write_to(writer)
+    }
+
+    fn read_from(reader: &mut dyn Read) -> io::Result<Self> {
+        Ok(u8::read_from(reader)? != 0)
+    }
+}
+
+impl Cacheable for char {
+    fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        (*self as u32).write_to(writer)
+    }
+
+    fn read_from(reader: &mut dyn Read) -> io::Result<Self> {
+        std::char::from_u32(u32::read_from(reader)?)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid char"))
+    }
+}
+
+impl Cacheable for String {
+    fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.len().write_to(writer)?;
+        writer.write_all(self.as_bytes())
+    }
+
+    fn read_from(reader: &mut dyn Read) -> io::Result<Self> {
+        // NOTE: the length comes from the file, so it is not trusted to allocate the buffer
+        let len = u64::read_from(reader)?;
+        let mut buf = Vec::new();
+        reader.take(len).read_to_end(&mut buf)?;
+        if buf.len() as u64 != len {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "string shorter than its length",
+            ));
+        }
+
+        String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+// the most elements a Vec reserves before reading them, the rest are added as they are read
+const MAX_PREALLOCATED: usize = 1024;
+
+impl<T> Cacheable for Vec<T>
+where
+    T: Cacheable,
+{
+    fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.len().write_to(writer)?;
+        for val in self {
+            val.write_to(writer)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_from(reader: &mut dyn Read) -> io::Result<Self> {
+        // NOTE: the length comes from the file, so only a bounded capacity is reserved up front
+        let len = usize::read_from(reader)?;
+        let mut v = Vec::with_capacity(len.min(MAX_PREALLOCATED));
+        for _ in 0..len {
+            v.push(T::read_from(reader)?);
+        }
+
+        Ok(v)
+    }
+}
+
+impl<T> Cacheable for Option<T>
+where
+    T: Cacheable,
+{
+    fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+        match self {
+            Some(val) => {
+                true.write_to(writer)?;
+                val.write_to(writer)
+            }
+            None => false.write_to(writer),
+        }
+    }
+
+    fn read_from(reader: &mut dyn Read) -> io::Result<Self> {
+        if bool::read_from(reader)? {
+            Ok(Some(T::read_from(reader)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+macro_rules! cacheable_tuple {
+    ($($name:ident),*) => {
+        #[allow(non_snake_case)]
+        impl<$($name),*> Cacheable for ($($name,)*)
+        where
+            $($name: Cacheable),*
+        {
+            fn write_to(&self, writer: &mut dyn Write) -> io::Result<()> {
+                let ($($name,)*) = self;
+                $($name.write_to(writer)?;)*
+                Ok(())
+            }
+
+            fn read_from(reader: &mut dyn Read) -> io::Result<Self> {
+                Ok(($($name::read_from(reader)?,)*))
+            }
+        }
+    };
+}
+
+cacheable_tuple!(A, B);
+cacheable_tuple!(A, B, C);
+cacheable_tuple!(A, B, C, D);
+
+/// Cache is an iterator that stores the elements of its inner iterator in memory during the
+/// first full pass, and returns them from memory on later passes.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<usize> = (0..4).map(|x| x * 2).cache().repeat(2).collect();
+/// assert_eq!(v, vec![0, 2, 4, 6, 0, 2, 4, 6]);
+/// ```
+///
+/// A pass that stops early does not fill the cache, and the next pass reads from the inner
+/// iterator again.
+///
+/// A checkpoint saved while replaying only stores the position, so when it is restored
+/// without a filled cache, for example in a new process, the cache is filled again from the
+/// inner iterator first.
+#[allow(clippy::type_complexity)]
+pub struct Cache<I>
+where
+    I: Iterator,
+{
+    iter: I,
+    cache: Arc<Mutex<Option<Arc<Vec<<I as Iterator>::Item>>>>>,
+    state: CacheState<<I as Iterator>::Item>,
+}
+
+enum CacheState<T> {
+    Unstarted,
+    Filling(Vec<T>),
+    Replaying(Arc<Vec<T>>, usize),
+    Done,
+}
+
+impl<I> Cache<I>
+where
+    I: Iterator,
+{
+    pub(crate) fn new(iter: I) -> Cache<I> {
+        Cache {
+            iter,
+            cache: Arc::new(Mutex::new(None)),
+            state: CacheState::Unstarted,
+        }
+    }
+}
+
+impl<I> fmt::Debug for Cache<I>
+where
+    I: Iterator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Cache {{ completed: {} }}",
+            self.cache.lock().unwrap().is_some()
+        )
+    }
+}
+
+impl<I> Reiterable for Cache<I>
+where
+    I: Reiterable,
+    <I as Iterator>::Item: Clone,
+{
+    fn reiterate(&self, epoch: usize) -> Cache<I> {
+        Cache {
+            iter: self.iter.reiterate(epoch),
+            cache: Arc::clone(&self.cache),
+            state: CacheState::Unstarted,
+        }
+    }
+}
+
+impl<I> Checkpoint for Cache<I>
+where
+    I: Iterator + Checkpoint,
+    <I as Iterator>::Item: Clone + Cacheable,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        match self.state {
+            CacheState::Unstarted => 0u8.write_to(writer),
+            CacheState::Filling(ref buffer) => {
+                1u8.write_to(writer)?;
+                buffer.write_to(writer)?;
+                self.iter.save_state(writer)
+            }
+            CacheState::Replaying(_, index) => {
+                2u8.write_to(writer)?;
+                index.write_to(writer)
+            }
+            CacheState::Done => 3u8.write_to(writer),
+        }
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.state = match u8::read_from(reader)? {
+            0 => CacheState::Unstarted,
+            1 => {
+                let buffer = Vec::read_from(reader)?;
+                self.iter.restore_state(reader)?;
+                CacheState::Filling(buffer)
+            }
+            2 => {
+                let index = usize::read_from(reader)?;
+                let (iter, mut cache) = (&mut self.iter, self.cache.lock().unwrap());
+                let cache = cache.get_or_insert_with(|| Arc::new(iter.collect()));
+                CacheState::Replaying(Arc::clone(cache), index)
+            }
+            3 => CacheState::Done,
+            _ => return Err(invalid_state("unknown cache state")),
+        };
+
+        Ok(())
+    }
+}
+
+impl<I> Iterator for Cache<I>
+where
+    I: Iterator,
+    <I as Iterator>::Item: Clone,
+{
+    type Item = <I as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let CacheState::Unstarted = self.state {
+            self.state = match self.cache.lock().unwrap().as_ref() {
+                Some(cache) => CacheState::Replaying(Arc::clone(cache), 0),
+                None => CacheState::Filling(Vec::new()),
+            };
+        }
+
+        match self.state {
+            CacheState::Filling(ref mut buffer) => match self.iter.next() {
+                Some(val) => {
+                    buffer.push(val.clone());
+                    Some(val)
+                }
+                None => {
+                    let buffer = std::mem::take(buffer);
+                    self.cache
+                        .lock()
+                        .unwrap()
+                        .get_or_insert_with(|| Arc::new(buffer));
+                    self.state = CacheState::Done;
+                    None
+                }
+            },
+            CacheState::Replaying(ref cache, ref mut index) => {
+                let val = cache.get(*index).cloned();
+                *index += 1;
+                val
+            }
+            _ => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.state {
+            CacheState::Unstarted => match self.cache.lock().unwrap().as_ref() {
+                Some(cache) => (cache.len(), Some(cache.len())),
+                None => self.iter.size_hint(),
+            },
+            CacheState::Filling(_) => self.iter.size_hint(),
+            CacheState::Replaying(ref cache, index) => {
+                let n = cache.len().saturating_sub(index);
+                (n, Some(n))
+            }
+            CacheState::Done => (0, Some(0)),
+        }
+    }
+}
+
+impl<I> ExactSizeIterator for Cache<I>
+where
+    I: ExactSizeIterator,
+    <I as Iterator>::Item: Clone,
+{
+}
+
+/// CacheTo is an iterator that writes the elements of its inner iterator to a file during the
+/// first full pass, and reads them from the file on later passes, including in later runs.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let path = std::env::temp_dir().join("datasets_cache_to_doctest");
+/// # let _ = std::fs::remove_file(&path);
+/// # let _ = std::fs::remove_file(std::env::temp_dir().join("datasets_cache_to_doctest.completed"));
+///
+/// let v: Vec<String> = (0..4).map(|x| x.to_string()).cache_to(&path).collect();
+/// assert_eq!(v, vec!["0", "1", "2", "3"]);
+///
+/// // the cache is complete, so the inner iterator is not used
+/// let v: Vec<String> = (0..0).map(|x: usize| x.to_string()).cache_to(&path).collect();
+/// assert_eq!(v, vec!["0", "1", "2", "3"]);
+/// ```
+///
+/// Like `download`, a `{file}.completed` marker is created next to the cache once a full pass
+/// has been written. A cache without the marker, for example from an interrupted run, is
+/// ignored and overwritten.
+///
+/// Errors while writing the cache are reported and the elements are still returned from the
+/// inner iterator.
+///
+/// A checkpoint saved while reading the cache stores the position in the file. One saved while
+/// writing it only stores the position of the inner iterator, so the restored pass does not
+/// complete the cache, and the next pass writes it again.
+pub struct CacheTo<I>
+where
+    I: Iterator,
+{
+    iter: I,
+    path: PathBuf,
+    state: CacheToState,
+}
+
+enum CacheToState {
+    Unstarted,
+    Writing(BufWriter<File>),
+    Reading(BufReader<File>),
+    Passthrough,
+    Done,
+}
+
+impl<I> CacheTo<I>
+where
+    I: Iterator,
+{
+    pub(crate) fn new(iter: I, path: &Path) -> CacheTo<I> {
+        CacheTo {
+            iter,
+            path: path.to_path_buf(),
+            state: CacheToState::Unstarted,
+        }
+    }
+
+    fn completed_file_location(&self) -> PathBuf {
+        let mut file_name = self
+            .path
+            .file_name()
+            .map(|s| s.to_os_string())
+            .unwrap_or_default();
+        file_name.push(".completed");
+        self.path.with_file_name(file_name)
+    }
+
+    fn start(&mut self) -> io::Result<CacheToState> {
+        if self.completed_file_location().exists() {
+            return Ok(CacheToState::Reading(BufReader::new(File::open(
+                &self.path,
+            )?)));
+        }
+
+        if self.path.exists() {
+            eprintln!("Ignoring incomplete cache at {:?}", self.path);
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        Ok(CacheToState::Writing(BufWriter::new(File::create(
+            &self.path,
+        )?)))
+    }
+
+    fn abandon(&mut self, err: &io::Error) {
+        eprintln!("Unable to write cache to {:?}: {}", self.path, err);
+        let _ = fs::remove_file(&self.path);
+        self.state = CacheToState::Passthrough;
+    }
+}
+
+impl<I> fmt::Debug for CacheTo<I>
+where
+    I: Iterator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CacheTo {{ path: {:?} }}", self.path)
+    }
+}
+
+impl<I> Reiterable for CacheTo<I>
+where
+    I: Reiterable,
+    <I as Iterator>::Item: Cacheable,
+{
+    fn reiterate(&self, epoch: usize) -> CacheTo<I> {
+        CacheTo::new(self.iter.reiterate(epoch), &self.path)
+    }
+}
+
+impl<I> Checkpoint for CacheTo<I>
+where
+    I: Iterator + Checkpoint,
+    <I as Iterator>::Item: Cacheable,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        match self.state {
+            CacheToState::Unstarted => 0u8.write_to(writer),
+            CacheToState::Writing(_) | CacheToState::Passthrough => {
+                1u8.write_to(writer)?;
+                self.iter.save_state(writer)
+            }
+            CacheToState::Reading(ref reader) => {
+                // NOTE: the position of the file is ahead by what is buffered but not read yet
+                let mut file: &File = reader.get_ref();
+                let position = file.stream_position()?;
+                2u8.write_to(writer)?;
+                (position - reader.buffer().len() as u64).write_to(writer)
+            }
+            CacheToState::Done => 3u8.write_to(writer),
+        }
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.state = match u8::read_from(reader)? {
+            0 => CacheToState::Unstarted,
+            1 => {
+                self.iter.restore_state(reader)?;
+                CacheToState::Passthrough
+            }
+            2 => {
+                let position = u64::read_from(reader)?;
+                if !self.completed_file_location().exists() {
+                    return Err(invalid_state("the cache file is not complete"));
+                }
+
+                let mut file = File::open(&self.path)?;
+                file.seek(SeekFrom::Start(position))?;
+                CacheToState::Reading(BufReader::new(file))
+            }
+            3 => CacheToState::Done,
+            _ => return Err(invalid_state("unknown cache state")),
+        };
+
+        Ok(())
+    }
+}
+
+impl<I> Iterator for CacheTo<I>
+where
+    I: Iterator,
+    <I as Iterator>::Item: Cacheable,
+{
+    type Item = <I as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let CacheToState::Unstarted = self.state {
+            self.state = match self.start() {
+                Ok(state) => state,
+                Err(err) => {
+                    eprintln!("Unable to use cache at {:?}: {}", self.path, err);
+                    CacheToState::Passthrough
+                }
+            };
+        }
+
+        match self.state {
+            CacheToState::Writing(ref mut writer) => match self.iter.next() {
+                Some(val) => {
+                    if let Err(err) = val.write_to(writer) {
+                        self.abandon(&err);
+                    }
+
+                    Some(val)
+                }
+                None => {
+                    let completed = writer
+                        .flush()
+                        .and_then(|_| File::create(self.completed_file_location()).map(|_| ()));
+
+                    match completed {
+                        Ok(_) => self.state = CacheToState::Done,
+                        Err(err) => self.abandon(&err),
+                    }
+
+                    None
+                }
+            },
+            CacheToState::Reading(ref mut reader) => match Self::Item::read_from(reader) {
+                Ok(val) => Some(val),
+                Err(err) => {
+                    if err.kind() != io::ErrorKind::UnexpectedEof {
+                        eprintln!("Unable to read cache from {:?}: {}", self.path, err);
+                    }
+
+                    self.state = CacheToState::Done;
+                    None
+                }
+            },
+            CacheToState::Passthrough => self.iter.next(),
+            _ => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.state {
+            // NOTE: the number of elements in a completed cache is only known by reading it
+            CacheToState::Unstarted if self.completed_file_location().exists() => (0, None),
+            CacheToState::Reading(_) => (0, None),
+            CacheToState::Done => (0, Some(0)),
+            _ => self.iter.size_hint(),
+        }
+    }
+}
@@ -0,0 +1,53 @@
+/// The number of elements in a dataset, returned by `Dataset::cardinality`.
+///
+/// This is derived from `Iterator::size_hint`, following the convention of `std` that an
+/// iterator returning `(usize::MAX, None)` never ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cardinality {
+    /// the dataset has exactly this many elements left
+    Known(usize),
+    /// the number of elements cannot be known without iterating
+    Unknown,
+    /// the dataset never ends
+    Infinite,
+}
+
+impl Cardinality {
+    /// the cardinality described by a size hint.
+    pub fn from_size_hint(hint: (usize, Option<usize>)) -> Cardinality {
+        match hint {
+            (lower, Some(upper)) if lower == upper => Cardinality::Known(lower),
+            (usize::MAX, None) => Cardinality::Infinite,
+            _ => Cardinality::Unknown,
+        }
+    }
+}
+
+/// the size hint of an iterator that never ends.
+pub(crate) const INFINITE: (usize, Option<usize>) = (usize::MAX, None);
+
+/// applies a count that is increasing in the number of elements to both bounds of `hint`,
+/// keeping infinite hints infinite.
+pub(crate) fn map_hint<F>(hint: (usize, Option<usize>), f: F) -> (usize, Option<usize>)
+where
+    F: Fn(usize) -> usize,
+{
+    if hint == INFINITE {
+        INFINITE
+    } else {
+        (f(hint.0), hint.1.map(f))
+    }
+}
+
+/// the size hint of two iterators chained together.
+pub(crate) fn add_hints(
+    (lo1, hi1): (usize, Option<usize>),
+    (lo2, hi2): (usize, Option<usize>),
+) -> (usize, Option<usize>) {
+    let hi = match (hi1, hi2) {
+        (Some(hi1), Some(hi2)) => hi1.checked_add(hi2),
+        _ => None,
+    };
+
+    (lo1.saturating_add(lo2), hi)
+}
@@ -0,0 +1,84 @@
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::vec;
+
+use crate::dataset::Cacheable;
+
+/// Checkpoint is implemented by datasets that can save their position, and restore it later
+/// to resume iterating from the same element, for example after a training job is restarted.
+///
+/// `restore_state` is expected to be called on a dataset that was created in the same way as
+/// the one the state was saved from, and has not been iterated yet.
+///
+/// ```
+/// use datasets::{Checkpoint, Dataset};
+///
+/// let mut data = (0..16).shuffle(4, 0).batch(2, false);
+/// data.next();
+/// data.next();
+///
+/// let mut state = Vec::new();
+/// data.save_state(&mut state).unwrap();
+///
+/// let mut restored = (0..16).shuffle(4, 0).batch(2, false);
+/// restored.restore_state(&mut &state[..]).unwrap();
+///
+/// assert_eq!(restored.collect::<Vec<_>>(), data.collect::<Vec<_>>());
+/// ```
+///
+/// Besides the adapters of this crate, `vec::IntoIter`, `Range` and the loaders are
+/// checkpointable. The adapters of `Iterator`, like `map`, `filter` or `enumerate`, do not
+/// give access to their inner iterator, so they are not, use `checkpointable_map` and
+/// `checkpointable_filter` instead. The state of adapters that run on other threads, like
+/// `prefetch` and `par_map`, cannot be saved, so those are not checkpointable either.
+pub trait Checkpoint {
+    /// write the current position of this dataset to `writer`.
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()>;
+
+    /// restore a position written by `save_state` from `reader`.
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()>;
+}
+
+impl<T> Checkpoint for vec::IntoIter<T> {
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.len().write_to(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let remaining = usize::read_from(reader)?;
+        if remaining > self.len() {
+            return Err(invalid_state("more elements remaining than available"));
+        }
+
+        let skip = self.len() - remaining;
+        if skip > 0 {
+            self.nth(skip - 1);
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Checkpoint for Range<T>
+where
+    T: Cacheable,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.start.write_to(writer)?;
+        self.end.write_to(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.start = T::read_from(reader)?;
+        self.end = T::read_from(reader)?;
+        Ok(())
+    }
+}
+
+/// an error for a state that does not match the dataset it is restored into.
+pub(crate) fn invalid_state(message: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid checkpoint state: {}", message),
+    )
+}
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+use std::io::{self, Read, Write};
+use std::vec;
+
+use crate::dataset::{Cacheable, Checkpoint, Reiterable};
+
+/// GroupByWindow is an iterator that groups the elements of its inner iterator by a key, and
+/// returns a window for a key as soon as it has `window_size` elements, like
+/// `tf.data.experimental.group_by_window`.
+///
+/// Once the inner iterator is exhausted, the remaining partial windows are returned in the
+/// order their keys were first seen.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<(usize, Vec<usize>)> = (0..7).group_by_window(|x| x % 2, 2).collect();
+///
+/// assert_eq!(
+///     v,
+///     vec![
+///         (0, vec![0, 2]),
+///         (1, vec![1, 3]),
+///         (0, vec![4, 6]),
+///         (1, vec![5]),
+///     ]
+/// );
+/// ```
+pub struct GroupByWindow<I, K, F>
+where
+    I: Iterator,
+{
+    iter: I,
+    key_fn: F,
+    window_size: usize,
+    // windows in the order their keys were first seen, and the index of each key
+    windows: Vec<(K, Vec<<I as Iterator>::Item>)>,
+    index: HashMap<K, usize>,
+    flushed: usize,
+    exhausted: bool,
+}
+
+impl<I, K, F> GroupByWindow<I, K, F>
+where
+    I: Iterator,
+    K: Eq + Hash + Clone,
+    F: FnMut(&<I as Iterator>::Item) -> K,
+{
+    pub(crate) fn new(iter: I, key_fn: F, window_size: usize) -> GroupByWindow<I, K, F> {
+        assert!(
+            window_size > 0,
+            "group_by_window: window_size must be greater than 0"
+        );
+
+        GroupByWindow {
+            iter,
+            key_fn,
+            window_size,
+            windows: Vec::new(),
+            index: HashMap::new(),
+            flushed: 0,
+            exhausted: false,
+        }
+    }
+}
+
+impl<I, K, F> fmt::Debug for GroupByWindow<I, K, F>
+where
+    I: Iterator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "GroupByWindow {{ window_size: {}, keys: {} }}",
+            self.window_size,
+            self.windows.len()
+        )
+    }
+}
+
+impl<I, K, F> Reiterable for GroupByWindow<I, K, F>
+where
+    I: Reiterable,
+    K: Eq + Hash + Clone,
+    F: FnMut(&<I as Iterator>::Item) -> K + Clone,
+{
+    fn reiterate(&self, epoch: usize) -> GroupByWindow<I, K, F> {
+        GroupByWindow::new(
+            self.iter.reiterate(epoch),
+            self.key_fn.clone(),
+            self.window_size,
+        )
+    }
+}
+
+impl<I, K, F> Checkpoint for GroupByWindow<I, K, F>
+where
+    I: Iterator + Checkpoint,
+    <I as Iterator>::Item: Cacheable,
+    K: Eq + Hash + Clone + Cacheable,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.exhausted.write_to(writer)?;
+        self.flushed.write_to(writer)?;
+        self.windows.len().write_to(writer)?;
+        for (key, window) in &self.windows {
+            key.write_to(writer)?;
+            window.write_to(writer)?;
+        }
+
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.exhausted = bool::read_from(reader)?;
+        self.flushed = usize::read_from(reader)?;
+
+        // NOTE: the length comes from the state, so nothing is reserved for it up front
+        let len = usize::read_from(reader)?;
+        self.windows = Vec::new();
+        self.index = HashMap::new();
+        for i in 0..len {
+            let key = K::read_from(reader)?;
+            self.index.insert(key.clone(), i);
+            self.windows.push((key, Vec::read_from(reader)?));
+        }
+
+        self.iter.restore_state(reader)
+    }
+}
+
+impl<I, K, F> Iterator for GroupByWindow<I, K, F>
+where
+    I: Iterator,
+    K: Eq + Hash + Clone,
+    F: FnMut(&<I as Iterator>::Item) -> K,
+{
+    type Item = (K, Vec<<I as Iterator>::Item>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.exhausted {
+            let val = match self.iter.next() {
+                Some(val) => val,
+                None => {
+                    self.exhausted = true;
+                    break;
+                }
+            };
+
+            let key = (self.key_fn)(&val);
+            let i = match self.index.get(&key) {
+                Some(i) => *i,
+                None => {
+                    self.index.insert(key.clone(), self.windows.len());
+                    self.windows
+                        .push((key, Vec::with_capacity(self.window_size)));
+                    self.windows.len() - 1
+                }
+            };
+
+            let (ref key, ref mut window) = self.windows[i];
+            window.push(val);
+            if window.len() == self.window_size {
+                let window = std::mem::replace(window, Vec::with_capacity(self.window_size));
+                return Some((key.clone(), window));
+            }
+        }
+
+        // the inner iterator is exhausted, return the remaining partial windows
+        while self.flushed < self.windows.len() {
+            let (ref key, ref mut window) = self.windows[self.flushed];
+            self.flushed += 1;
+
+            if !window.is_empty() {
+                return Some((key.clone(), std::mem::take(window)));
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let windows = &self.windows[self.flushed..];
+        if self.exhausted {
+            let n = windows.iter().filter(|(_, w)| !w.is_empty()).count();
+            return (n, Some(n));
+        }
+
+        // the number of windows depends on the keys, so only the upper bound of one window per
+        // element is known
+        let buffered: usize = windows.iter().map(|(_, w)| w.len()).sum();
+        let (_, upper) = self.iter.size_hint();
+        (0, upper.and_then(|n| n.checked_add(buffered)))
+    }
+}
+
+/// GroupByReducer is an iterator that groups all elements of its inner iterator by a key, and
+/// reduces each group to a single value, like `tf.data.experimental.group_by_reducer`.
+///
+/// For each key, `init` creates the initial state, `reduce` adds an element to it, and
+/// `finalize` turns the final state into the returned value. The inner iterator is consumed
+/// entirely on the first call to `next`, and the results are returned in the order their keys
+/// were first seen.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// // the mean of each class
+/// let v: Vec<(u8, f64)> = vec![(1.0, 0), (2.0, 1), (3.0, 0), (6.0, 1)]
+///     .into_iter()
+///     .group_by_reducer(
+///         |(_, label)| *label,
+///         |_| (0.0, 0),
+///         |(sum, count), (x, _)| {
+///             *sum += x;
+///             *count += 1;
+///         },
+///         |label, (sum, count)| (label, sum / count as f64),
+///     )
+///     .collect();
+///
+/// assert_eq!(v, vec![(0, 2.0), (1, 4.0)]);
+/// ```
+pub struct GroupByReducer<I, K, S, B, F, N, R, Z>
+where
+    I: Iterator,
+{
+    iter: I,
+    key_fn: F,
+    init: N,
+    reduce: R,
+    finalize: Z,
+    states: Option<vec::IntoIter<(K, S)>>,
+    results: std::marker::PhantomData<B>,
+}
+
+impl<I, K, S, B, F, N, R, Z> GroupByReducer<I, K, S, B, F, N, R, Z>
+where
+    I: Iterator,
+    K: Eq + Hash + Clone,
+    F: FnMut(&<I as Iterator>::Item) -> K,
+    N: FnMut(&K) -> S,
+    R: FnMut(&mut S, <I as Iterator>::Item),
+    Z: FnMut(K, S) -> B,
+{
+    pub(crate) fn new(
+        iter: I,
+        key_fn: F,
+        init: N,
+        reduce: R,
+        finalize: Z,
+    ) -> GroupByReducer<I, K, S, B, F, N, R, Z> {
+        GroupByReducer {
+            iter,
+            key_fn,
+            init,
+            reduce,
+            finalize,
+            states: None,
+            results: std::marker::PhantomData,
+        }
+    }
+
+    fn reduce_all(&mut self) -> vec::IntoIter<(K, S)> {
+        let mut states: Vec<(K, S)> = Vec::new();
+        let mut index: HashMap<K, usize> = HashMap::new();
+
+        for val in &mut self.iter {
+            let key = (self.key_fn)(&val);
+            let i = match index.get(&key) {
+                Some(i) => *i,
+                None => {
+                    let state = (self.init)(&key);
+                    index.insert(key.clone(), states.len());
+                    states.push((key, state));
+                    states.len() - 1
+                }
+            };
+
+            (self.reduce)(&mut states[i].1, val);
+        }
+
+        states.into_iter()
+    }
+}
+
+impl<I, K, S, B, F, N, R, Z> fmt::Debug for GroupByReducer<I, K, S, B, F, N, R, Z>
+where
+    I: Iterator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "GroupByReducer {{ reduced: {} }}", self.states.is_some())
+    }
+}
+
+impl<I, K, S, B, F, N, R, Z> Reiterable for GroupByReducer<I, K, S, B, F, N, R, Z>
+where
+    I: Reiterable,
+    K: Eq + Hash + Clone,
+    F: FnMut(&<I as Iterator>::Item) -> K + Clone,
+    N: FnMut(&K) -> S + Clone,
+    R: FnMut(&mut S, <I as Iterator>::Item) + Clone,
+    Z: FnMut(K, S) -> B + Clone,
+{
+    fn reiterate(&self, epoch: usize) -> GroupByReducer<I, K, S, B, F, N, R, Z> {
+        GroupByReducer::new(
+            self.iter.reiterate(epoch),
+            self.key_fn.clone(),
+            self.init.clone(),
+            self.reduce.clone(),
+            self.finalize.clone(),
+        )
+    }
+}
+
+impl<I, K, S, B, F, N, R, Z> Iterator for GroupByReducer<I, K, S, B, F, N, R, Z>
+where
+    I: Iterator,
+    K: Eq + Hash + Clone,
+    F: FnMut(&<I as Iterator>::Item) -> K,
+    N: FnMut(&K) -> S,
+    R: FnMut(&mut S, <I as Iterator>::Item),
+    Z: FnMut(K, S) -> B,
+{
+    type Item = B;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.states.is_none() {
+            self.states = Some(self.reduce_all());
+        }
+
+        let (key, state) = self.states.as_mut()?.next()?;
+        Some((self.finalize)(key, state))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.states {
+            Some(ref states) => states.size_hint(),
+            None => (0, self.iter.size_hint().1),
+        }
+    }
+}
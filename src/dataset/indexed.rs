@@ -0,0 +1,390 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::vec;
+
+use rand::Rng;
+
+use crate::dataset::checkpoint::invalid_state;
+use crate::dataset::repeat::epoch_seed;
+use crate::dataset::shuffle_all::Permutation;
+use crate::dataset::{Cacheable, Checkpoint, CountingRng, Reiterable};
+
+/// An IndexedDataset is a dataset that supports random access to its elements, like the
+/// map-style datasets of PyTorch.
+///
+/// `len()` is the number of elements, from `ExactSizeIterator`, and `get(index)` returns the
+/// element at `index` without moving the position of iteration. Both only consider the elements
+/// that have not been returned by `next` yet.
+///
+/// - `permuted(seed)`: all elements in a uniformly random order, without collecting them
+/// - `random_sample(num_samples, seed)`: `num_samples` elements chosen uniformly with replacement
+/// - `subset(indices)`: the elements at `indices`, in that order
+///
+/// ```
+/// use datasets::IndexedDataset;
+///
+/// let mut data = vec!['a', 'b', 'c', 'd'].into_iter();
+/// assert_eq!(data.len(), 4);
+/// assert_eq!(data.get(2), Some('c'));
+///
+/// data.next();
+/// assert_eq!(data.get(2), Some('d'));
+/// assert_eq!(data.get(3), None);
+/// ```
+pub trait IndexedDataset: ExactSizeIterator {
+    /// returns the element at `index`, or `None` if it is out of bounds.
+    fn get(&mut self, index: usize) -> Option<Self::Item>;
+
+    /// permuted
+    fn permuted(self, seed: u64) -> Permuted<Self>
+    where
+        Self: Sized,
+    {
+        Permuted::new(self, seed)
+    }
+
+    /// random_sample
+    fn random_sample(self, num_samples: usize, seed: u64) -> RandomSample<Self>
+    where
+        Self: Sized,
+    {
+        RandomSample::new(self, num_samples, seed)
+    }
+
+    /// subset
+    ///
+    /// This will panic if any of the indices is out of bounds.
+    fn subset(self, indices: Vec<usize>) -> Subset<Self>
+    where
+        Self: Sized,
+    {
+        Subset::new(self, indices)
+    }
+}
+
+impl<T> IndexedDataset for vec::IntoIter<T>
+where
+    T: Clone,
+{
+    fn get(&mut self, index: usize) -> Option<T> {
+        self.as_slice().get(index).cloned()
+    }
+}
+
+/// Permuted is an iterator that returns all elements of an `IndexedDataset` in a uniformly
+/// random order, reading each element with `get` when it is returned.
+///
+/// ```
+/// use datasets::IndexedDataset;
+///
+/// let mut v: Vec<usize> = (0..8).collect::<Vec<_>>().into_iter().permuted(0).collect();
+/// assert_ne!(v, (0..8).collect::<Vec<usize>>());
+///
+/// v.sort();
+/// assert_eq!(v, (0..8).collect::<Vec<usize>>());
+/// ```
+///
+/// When repeated, every epoch is shuffled with a different seed derived from `seed`, unless
+/// `reshuffle_each_iteration` is set to false.
+pub struct Permuted<D> {
+    dataset: D,
+    permutation: Permutation,
+    seed: u64,
+    reshuffle_each_iteration: bool,
+}
+
+impl<D> Permuted<D>
+where
+    D: IndexedDataset,
+{
+    fn new(dataset: D, seed: u64) -> Permuted<D> {
+        Permuted {
+            permutation: Permutation::new(dataset.len(), seed),
+            dataset,
+            seed,
+            reshuffle_each_iteration: true,
+        }
+    }
+
+    /// when set to false, every epoch of a repeated dataset returns elements in the same order.
+    ///
+    /// defaults to true.
+    pub fn reshuffle_each_iteration(mut self, reshuffle_each_iteration: bool) -> Permuted<D> {
+        self.reshuffle_each_iteration = reshuffle_each_iteration;
+        self
+    }
+}
+
+impl<D> fmt::Debug for Permuted<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Permuted {{ permutation: {:?} }}", self.permutation)
+    }
+}
+
+impl<D> Reiterable for Permuted<D>
+where
+    D: IndexedDataset + Reiterable,
+{
+    fn reiterate(&self, epoch: usize) -> Permuted<D> {
+        let seed = if self.reshuffle_each_iteration {
+            epoch_seed(self.seed, epoch)
+        } else {
+            self.seed
+        };
+
+        let mut permuted = Permuted::new(self.dataset.reiterate(epoch), seed);
+        permuted.seed = self.seed;
+        permuted.reshuffle_each_iteration = self.reshuffle_each_iteration;
+        permuted
+    }
+}
+
+impl<D> Checkpoint for Permuted<D>
+where
+    D: IndexedDataset,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.permutation.position().write_to(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        // NOTE: the permutation is drawn again up to the saved position, without reading
+        let position = usize::read_from(reader)?;
+        if position < self.permutation.position() || position > self.dataset.len() {
+            return Err(invalid_state("permuted position out of range"));
+        }
+
+        while self.permutation.position() < position {
+            self.permutation.next();
+        }
+
+        Ok(())
+    }
+}
+
+impl<D> Iterator for Permuted<D>
+where
+    D: IndexedDataset,
+{
+    type Item = <D as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.permutation.next()?;
+        self.dataset.get(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.permutation.size_hint()
+    }
+}
+
+impl<D> ExactSizeIterator for Permuted<D> where D: IndexedDataset {}
+
+/// RandomSample is an iterator that returns `num_samples` elements of an `IndexedDataset`,
+/// each chosen uniformly at random with replacement.
+///
+/// ```
+/// use datasets::IndexedDataset;
+///
+/// let v: Vec<char> = vec!['a', 'b', 'c'].into_iter().random_sample(10, 0).collect();
+/// assert_eq!(v.len(), 10);
+/// assert!(v.iter().all(|c| ['a', 'b', 'c'].contains(c)));
+/// ```
+///
+/// Sampling from an empty dataset returns nothing.
+pub struct RandomSample<D> {
+    dataset: D,
+    num_samples: usize,
+    remaining: usize,
+    seed: u64,
+    rng: CountingRng,
+}
+
+impl<D> RandomSample<D>
+where
+    D: IndexedDataset,
+{
+    fn new(dataset: D, num_samples: usize, seed: u64) -> RandomSample<D> {
+        let remaining = if dataset.len() > 0 { num_samples } else { 0 };
+
+        RandomSample {
+            dataset,
+            num_samples,
+            remaining,
+            seed,
+            rng: CountingRng::new(seed),
+        }
+    }
+}
+
+impl<D> fmt::Debug for RandomSample<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "RandomSample {{ num_samples: {}, remaining: {}, rng: {:?} }}",
+            self.num_samples, self.remaining, self.rng
+        )
+    }
+}
+
+impl<D> Reiterable for RandomSample<D>
+where
+    D: IndexedDataset + Reiterable,
+{
+    fn reiterate(&self, epoch: usize) -> RandomSample<D> {
+        let mut sample =
+            RandomSample::new(self.dataset.reiterate(epoch), self.num_samples, self.seed);
+        sample.rng = CountingRng::new(epoch_seed(self.seed, epoch));
+        sample
+    }
+}
+
+impl<D> Checkpoint for RandomSample<D>
+where
+    D: IndexedDataset,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.remaining.write_to(writer)?;
+        self.rng.write_to(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.remaining = usize::read_from(reader)?;
+        self.rng = CountingRng::read_from(reader)?;
+        Ok(())
+    }
+}
+
+impl<D> Iterator for RandomSample<D>
+where
+    D: IndexedDataset,
+{
+    type Item = <D as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        self.remaining -= 1;
+        let index = self.rng.gen_range(0, self.dataset.len());
+        self.dataset.get(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<D> ExactSizeIterator for RandomSample<D> where D: IndexedDataset {}
+
+/// Subset is an `IndexedDataset` of the elements of another at a list of indices, in the
+/// order of the indices.
+///
+/// ```
+/// use datasets::IndexedDataset;
+///
+/// let data = vec!['a', 'b', 'c', 'd'].into_iter();
+///
+/// let v: Vec<char> = data.subset(vec![3, 0, 0]).collect();
+/// assert_eq!(v, vec!['d', 'a', 'a']);
+/// ```
+pub struct Subset<D> {
+    dataset: D,
+    indices: Vec<usize>,
+    position: usize,
+}
+
+impl<D> Subset<D>
+where
+    D: IndexedDataset,
+{
+    fn new(dataset: D, indices: Vec<usize>) -> Subset<D> {
+        let len = dataset.len();
+        if let Some(index) = indices.iter().find(|i| **i >= len) {
+            panic!(
+                "subset: index {} is out of bounds for a dataset of length {}",
+                index, len
+            );
+        }
+
+        Subset {
+            dataset,
+            indices,
+            position: 0,
+        }
+    }
+}
+
+impl<D> fmt::Debug for Subset<D> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Subset {{ len: {}, position: {} }}",
+            self.indices.len(),
+            self.position
+        )
+    }
+}
+
+impl<D> Reiterable for Subset<D>
+where
+    D: IndexedDataset + Reiterable,
+{
+    fn reiterate(&self, epoch: usize) -> Subset<D> {
+        Subset {
+            dataset: self.dataset.reiterate(epoch),
+            indices: self.indices.clone(),
+            position: 0,
+        }
+    }
+}
+
+impl<D> Checkpoint for Subset<D>
+where
+    D: IndexedDataset,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.position.write_to(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let position = usize::read_from(reader)?;
+        if position > self.indices.len() {
+            return Err(invalid_state("subset position out of range"));
+        }
+
+        self.position = position;
+        Ok(())
+    }
+}
+
+impl<D> Iterator for Subset<D>
+where
+    D: IndexedDataset,
+{
+    type Item = <D as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = *self.indices.get(self.position)?;
+        self.position += 1;
+        self.dataset.get(index)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.indices.len() - self.position;
+        (n, Some(n))
+    }
+}
+
+impl<D> ExactSizeIterator for Subset<D> where D: IndexedDataset {}
+
+impl<D> IndexedDataset for Subset<D>
+where
+    D: IndexedDataset,
+{
+    fn get(&mut self, index: usize) -> Option<Self::Item> {
+        let index = *self.indices.get(self.position + index)?;
+        self.dataset.get(index)
+    }
+}
@@ -0,0 +1,258 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::dataset::cardinality;
+use crate::dataset::checkpoint::invalid_state;
+use crate::dataset::{Cacheable, Checkpoint, Reiterable};
+
+/// Interleave is an iterator over datasets that returns their elements interleaved, like
+/// `tf.data.Dataset.interleave`.
+///
+/// Up to `cycle_length` of the inner datasets are open at a time, and `block_length`
+/// consecutive elements are taken from each before moving to the next. When one of them is
+/// exhausted, it is replaced by the next inner dataset.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<usize> = vec![vec![1, 2, 3], vec![4, 5], vec![6]]
+///     .into_iter()
+///     .interleave(2, 1)
+///     .collect();
+/// assert_eq!(v, vec![1, 4, 2, 5, 3, 6]);
+///
+/// let v: Vec<usize> = vec![vec![1, 2, 3], vec![4, 5], vec![6]]
+///     .into_iter()
+///     .interleave(2, 2)
+///     .collect();
+/// assert_eq!(v, vec![1, 2, 4, 5, 3, 6]);
+/// ```
+///
+/// The open inner datasets are not saved in a checkpoint, only which element of the outer
+/// dataset each one came from and how many of its elements were taken. Restoring replays the
+/// outer dataset with `reiterate` to open them again, skipping those elements with
+/// `Iterator::nth`.
+pub struct Interleave<I>
+where
+    I: Iterator,
+    <I as Iterator>::Item: IntoIterator,
+{
+    iter: I,
+    cycle_length: usize,
+    block_length: usize,
+    cycle: Vec<Option<<<I as Iterator>::Item as IntoIterator>::IntoIter>>,
+    current: usize,
+    taken: usize,
+    exhausted: bool,
+    // for every open inner dataset, the index of the outer element it came from, and the
+    // number of elements returned from it
+    positions: Vec<(usize, usize)>,
+    // the number of elements taken from the outer dataset
+    opened: usize,
+    epoch: usize,
+}
+
+impl<I> Interleave<I>
+where
+    I: Iterator,
+    <I as Iterator>::Item: IntoIterator,
+{
+    pub(crate) fn new(iter: I, cycle_length: usize, block_length: usize) -> Interleave<I> {
+        assert!(
+            cycle_length > 0,
+            "interleave: cycle_length must be greater than 0"
+        );
+        assert!(
+            block_length > 0,
+            "interleave: block_length must be greater than 0"
+        );
+
+        Interleave {
+            iter,
+            cycle_length,
+            block_length,
+            cycle: (0..cycle_length).map(|_| None).collect(),
+            current: 0,
+            taken: 0,
+            exhausted: false,
+            positions: vec![(0, 0); cycle_length],
+            opened: 0,
+            epoch: 0,
+        }
+    }
+
+    fn advance(&mut self) {
+        self.current = (self.current + 1) % self.cycle_length;
+        self.taken = 0;
+    }
+}
+
+impl<I> fmt::Debug for Interleave<I>
+where
+    I: Iterator,
+    <I as Iterator>::Item: IntoIterator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Interleave {{ cycle_length: {}, block_length: {} }}",
+            self.cycle_length, self.block_length
+        )
+    }
+}
+
+impl<I> Reiterable for Interleave<I>
+where
+    I: Reiterable,
+    <I as Iterator>::Item: IntoIterator,
+{
+    fn reiterate(&self, epoch: usize) -> Interleave<I> {
+        let mut interleave = Interleave::new(
+            self.iter.reiterate(epoch),
+            self.cycle_length,
+            self.block_length,
+        );
+        interleave.epoch = epoch;
+        interleave
+    }
+}
+
+impl<I> Checkpoint for Interleave<I>
+where
+    I: Reiterable + Checkpoint,
+    <I as Iterator>::Item: IntoIterator,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.cycle_length.write_to(writer)?;
+        self.current.write_to(writer)?;
+        self.taken.write_to(writer)?;
+        self.exhausted.write_to(writer)?;
+        self.opened.write_to(writer)?;
+        for (it, position) in self.cycle.iter().zip(&self.positions) {
+            it.as_ref().map(|_| *position).write_to(writer)?;
+        }
+
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        if usize::read_from(reader)? != self.cycle_length {
+            return Err(invalid_state("interleave cycle_length does not match"));
+        }
+
+        let current = usize::read_from(reader)?;
+        let taken = usize::read_from(reader)?;
+        let exhausted = bool::read_from(reader)?;
+        let opened = usize::read_from(reader)?;
+
+        let mut positions = Vec::new();
+        for _ in 0..self.cycle_length {
+            positions.push(Option::<(usize, usize)>::read_from(reader)?);
+        }
+
+        if current >= self.cycle_length
+            || positions
+                .iter()
+                .flatten()
+                .any(|(index, _)| *index >= opened)
+        {
+            return Err(invalid_state("interleave position out of range"));
+        }
+
+        // open the inner datasets again, in the order they were opened
+        let mut slots: Vec<usize> = (0..self.cycle_length)
+            .filter(|i| positions[*i].is_some())
+            .collect();
+        slots.sort_by_key(|i| positions[*i].map(|(index, _)| index));
+
+        let mut outer = self.iter.reiterate(self.epoch);
+        let mut next_index = 0;
+        for i in slots {
+            let (index, returned) = positions[i].unwrap();
+            let val = outer
+                .nth(index - next_index)
+                .ok_or_else(|| invalid_state("interleave outer dataset ended early"))?;
+            next_index = index + 1;
+
+            let mut it = val.into_iter();
+            if returned > 0 {
+                it.nth(returned - 1);
+            }
+
+            self.cycle[i] = Some(it);
+            self.positions[i] = (index, returned);
+        }
+
+        for (it, position) in self.cycle.iter_mut().zip(&positions) {
+            if position.is_none() {
+                *it = None;
+            }
+        }
+
+        self.current = current;
+        self.taken = taken;
+        self.exhausted = exhausted;
+        self.opened = opened;
+        self.iter.restore_state(reader)
+    }
+}
+
+impl<I> Iterator for Interleave<I>
+where
+    I: Iterator,
+    <I as Iterator>::Item: IntoIterator,
+{
+    type Item = <<I as Iterator>::Item as IntoIterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.exhausted && self.cycle.iter().all(Option::is_none) {
+                return None;
+            }
+
+            if self.cycle[self.current].is_none() && !self.exhausted {
+                match self.iter.next() {
+                    Some(val) => {
+                        self.cycle[self.current] = Some(val.into_iter());
+                        self.positions[self.current] = (self.opened, 0);
+                        self.opened += 1;
+                    }
+                    None => self.exhausted = true,
+                }
+            }
+
+            match self.cycle[self.current].as_mut().map(|it| it.next()) {
+                Some(Some(val)) => {
+                    self.positions[self.current].1 += 1;
+                    self.taken += 1;
+                    if self.taken == self.block_length {
+                        self.advance();
+                    }
+
+                    return Some(val);
+                }
+                Some(None) => {
+                    self.cycle[self.current] = None;
+                    self.advance();
+                }
+                None => self.advance(),
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let cycle = self
+            .cycle
+            .iter()
+            .flatten()
+            .map(Iterator::size_hint)
+            .fold((0, Some(0)), cardinality::add_hints);
+
+        // the lengths of datasets that were not started yet are unknown
+        if self.exhausted || self.iter.size_hint().1 == Some(0) {
+            cycle
+        } else {
+            (cycle.0, None)
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::dataset::{Checkpoint, Reiterable};
+
+/// Map is an iterator that applies a function to the elements of its inner iterator, like
+/// `Iterator::map`, but is `Checkpoint` and `Reiterable` when its inner iterator is.
+///
+/// ```
+/// use datasets::{Checkpoint, Dataset};
+///
+/// let mut data = (0..8).checkpointable_map(|x| x * 2).shuffle(4, 0);
+/// data.next();
+///
+/// let mut state = Vec::new();
+/// data.save_state(&mut state).unwrap();
+///
+/// let mut restored = (0..8).checkpointable_map(|x| x * 2).shuffle(4, 0);
+/// restored.restore_state(&mut &state[..]).unwrap();
+///
+/// assert_eq!(restored.collect::<Vec<_>>(), data.collect::<Vec<_>>());
+/// ```
+pub struct Map<I, F> {
+    iter: I,
+    f: F,
+}
+
+impl<I, F> Map<I, F> {
+    pub(crate) fn new(iter: I, f: F) -> Map<I, F> {
+        Map { iter, f }
+    }
+}
+
+impl<I, F> fmt::Debug for Map<I, F>
+where
+    I: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Map {{ iter: {:?} }}", self.iter)
+    }
+}
+
+impl<I, F, B> Reiterable for Map<I, F>
+where
+    I: Reiterable,
+    F: FnMut(<I as Iterator>::Item) -> B + Clone,
+{
+    fn reiterate(&self, epoch: usize) -> Map<I, F> {
+        Map::new(self.iter.reiterate(epoch), self.f.clone())
+    }
+}
+
+impl<I, F> Checkpoint for Map<I, F>
+where
+    I: Checkpoint,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.iter.restore_state(reader)
+    }
+}
+
+impl<I, F, B> Iterator for Map<I, F>
+where
+    I: Iterator,
+    F: FnMut(<I as Iterator>::Item) -> B,
+{
+    type Item = B;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.iter.next().map(&mut self.f)
+    }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        // NOTE: the skipped elements are not mapped, so random access sources can seek past them
+        self.iter.nth(n).map(&mut self.f)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I, F, B> ExactSizeIterator for Map<I, F>
+where
+    I: ExactSizeIterator,
+    F: FnMut(<I as Iterator>::Item) -> B,
+{
+}
+
+/// Filter is an iterator that returns the elements of its inner iterator that match a
+/// predicate, like `Iterator::filter`, but is `Checkpoint` and `Reiterable` when its inner
+/// iterator is.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<usize> = (0..8).checkpointable_filter(|x| x % 3 == 0).collect();
+/// assert_eq!(v, vec![0, 3, 6]);
+/// ```
+pub struct Filter<I, P> {
+    iter: I,
+    predicate: P,
+}
+
+impl<I, P> Filter<I, P> {
+    pub(crate) fn new(iter: I, predicate: P) -> Filter<I, P> {
+        Filter { iter, predicate }
+    }
+}
+
+impl<I, P> fmt::Debug for Filter<I, P>
+where
+    I: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Filter {{ iter: {:?} }}", self.iter)
+    }
+}
+
+impl<I, P> Reiterable for Filter<I, P>
+where
+    I: Reiterable,
+    P: FnMut(&<I as Iterator>::Item) -> bool + Clone,
+{
+    fn reiterate(&self, epoch: usize) -> Filter<I, P> {
+        Filter::new(self.iter.reiterate(epoch), self.predicate.clone())
+    }
+}
+
+impl<I, P> Checkpoint for Filter<I, P>
+where
+    I: Checkpoint,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.iter.restore_state(reader)
+    }
+}
+
+impl<I, P> Iterator for Filter<I, P>
+where
+    I: Iterator,
+    P: FnMut(&<I as Iterator>::Item) -> bool,
+{
+    type Item = <I as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let predicate = &mut self.predicate;
+        self.iter.find(|val| predicate(val))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
@@ -0,0 +1,250 @@
+use std::io::{self, Read, Write};
+
+use crate::dataset::{Batch, Checkpoint, Reiterable};
+
+/// PaddedBatch is an iterator that returns the sequences of its inner iterator in batches,
+/// with every sequence in a batch padded to the same length.
+///
+/// By default, sequences are padded to the length of the longest sequence in their batch.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let vals: Vec<Vec<Vec<usize>>> = vec![vec![1], vec![1, 2, 3], vec![1, 2]]
+///     .into_iter()
+///     .padded_batch(2, 0, false)
+///     .collect();
+///
+/// assert_eq!(vals.len(), 2);
+/// assert_eq!(vals[0], vec![vec![1, 0, 0], vec![1, 2, 3]]);
+/// assert_eq!(vals[1], vec![vec![1, 2]]);
+/// ```
+///
+/// `padded_length` pads to a fixed length instead, `with_lengths` and `with_mask` also return
+/// the original lengths of the sequences.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let (vals, mask) = vec![vec![1], vec![1, 2, 3]]
+///     .into_iter()
+///     .padded_batch(2, 0, false)
+///     .padded_length(4)
+///     .with_mask()
+///     .next()
+///     .unwrap();
+///
+/// assert_eq!(vals, vec![vec![1, 0, 0, 0], vec![1, 2, 3, 0]]);
+/// assert_eq!(mask[0], vec![true, false, false, false]);
+/// assert_eq!(mask[1], vec![true, true, true, false]);
+/// ```
+#[derive(Debug)]
+pub struct PaddedBatch<I, T>
+where
+    I: Iterator<Item = Vec<T>>,
+{
+    batch: Batch<I>,
+    padding_value: T,
+    padded_length: Option<usize>,
+}
+
+impl<I, T> PaddedBatch<I, T>
+where
+    I: Iterator<Item = Vec<T>>,
+    T: Clone,
+{
+    pub(crate) fn new(
+        iter: I,
+        batch_size: usize,
+        padding_value: T,
+        drop_remainder: bool,
+    ) -> PaddedBatch<I, T> {
+        PaddedBatch {
+            batch: Batch {
+                iter,
+                batch_size,
+                drop_remainder,
+            },
+            padding_value,
+            padded_length: None,
+        }
+    }
+
+    /// pad every sequence to `length` instead of the longest sequence in its batch.
+    ///
+    /// This will panic if a sequence longer than `length` is encountered.
+    pub fn padded_length(mut self, length: usize) -> PaddedBatch<I, T> {
+        self.padded_length = Some(length);
+        self
+    }
+
+    /// return the original lengths of the sequences along with each batch.
+    pub fn with_lengths(self) -> PaddedBatchWithLengths<I, T> {
+        PaddedBatchWithLengths { inner: self }
+    }
+
+    /// return a mask along with each batch, `true` for original values and `false` for padding.
+    pub fn with_mask(self) -> PaddedBatchWithMask<I, T> {
+        PaddedBatchWithMask { inner: self }
+    }
+
+    fn next_with_lengths(&mut self) -> Option<(Vec<Vec<T>>, Vec<usize>)> {
+        let mut batch = self.batch.next()?;
+        let lengths = pad(&mut batch, &self.padding_value, self.padded_length);
+        Some((batch, lengths))
+    }
+}
+
+impl<I, T> Reiterable for PaddedBatch<I, T>
+where
+    I: Reiterable + Iterator<Item = Vec<T>>,
+    T: Clone,
+{
+    fn reiterate(&self, epoch: usize) -> PaddedBatch<I, T> {
+        PaddedBatch {
+            batch: self.batch.reiterate(epoch),
+            padding_value: self.padding_value.clone(),
+            padded_length: self.padded_length,
+        }
+    }
+}
+
+impl<I, T> Checkpoint for PaddedBatch<I, T>
+where
+    I: Iterator<Item = Vec<T>> + Checkpoint,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.batch.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.batch.restore_state(reader)
+    }
+}
+
+impl<I, T> Iterator for PaddedBatch<I, T>
+where
+    I: Iterator<Item = Vec<T>>,
+    T: Clone,
+{
+    type Item = Vec<Vec<T>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_with_lengths().map(|(batch, _)| batch)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.batch.size_hint()
+    }
+}
+
+impl<I, T> ExactSizeIterator for PaddedBatch<I, T>
+where
+    I: ExactSizeIterator<Item = Vec<T>>,
+    T: Clone,
+{
+}
+
+/// PaddedBatchWithLengths is an iterator that returns padded batches along with the original
+/// lengths of the sequences in them, see `PaddedBatch::with_lengths`.
+#[derive(Debug)]
+pub struct PaddedBatchWithLengths<I, T>
+where
+    I: Iterator<Item = Vec<T>>,
+{
+    inner: PaddedBatch<I, T>,
+}
+
+impl<I, T> Iterator for PaddedBatchWithLengths<I, T>
+where
+    I: Iterator<Item = Vec<T>>,
+    T: Clone,
+{
+    type Item = (Vec<Vec<T>>, Vec<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next_with_lengths()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<I, T> ExactSizeIterator for PaddedBatchWithLengths<I, T>
+where
+    I: ExactSizeIterator<Item = Vec<T>>,
+    T: Clone,
+{
+}
+
+/// PaddedBatchWithMask is an iterator that returns padded batches along with a mask marking the
+/// original values, see `PaddedBatch::with_mask`.
+#[derive(Debug)]
+pub struct PaddedBatchWithMask<I, T>
+where
+    I: Iterator<Item = Vec<T>>,
+{
+    inner: PaddedBatch<I, T>,
+}
+
+impl<I, T> Iterator for PaddedBatchWithMask<I, T>
+where
+    I: Iterator<Item = Vec<T>>,
+    T: Clone,
+{
+    type Item = (Vec<Vec<T>>, Vec<Vec<bool>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next_with_lengths().map(|(batch, lengths)| {
+            let mask = batch
+                .iter()
+                .zip(lengths)
+                .map(|(seq, len)| (0..seq.len()).map(|i| i < len).collect())
+                .collect();
+
+            (batch, mask)
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<I, T> ExactSizeIterator for PaddedBatchWithMask<I, T>
+where
+    I: ExactSizeIterator<Item = Vec<T>>,
+    T: Clone,
+{
+}
+
+/// pads all sequences in `batch` to `length`, or the longest sequence if `length` is `None`,
+/// and returns the original lengths.
+pub(crate) fn pad<T>(batch: &mut [Vec<T>], padding_value: &T, length: Option<usize>) -> Vec<usize>
+where
+    T: Clone,
+{
+    let lengths: Vec<usize> = batch.iter().map(Vec::len).collect();
+    let max_length = lengths.iter().cloned().max().unwrap_or(0);
+
+    let length = match length {
+        Some(length) => {
+            assert!(
+                max_length <= length,
+                "padded_batch: sequence of length {} is longer than the padded length {}",
+                max_length,
+                length
+            );
+
+            length
+        }
+        None => max_length,
+    };
+
+    for seq in batch.iter_mut() {
+        seq.resize(length, padding_value.clone());
+    }
+
+    lengths
+}
@@ -0,0 +1,249 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+use crate::dataset::cardinality;
+use crate::dataset::Reiterable;
+
+/// ParMap is an iterator that applies a function to the elements of its inner iterator on a
+/// pool of worker threads, like `map` with `num_parallel_calls` in `tf.data`.
+///
+/// The inner iterator is still consumed on the calling thread, only the function runs on the
+/// workers. By default, elements are returned in the order of the inner iterator.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<usize> = (0..8).par_map(4, |x| x * 2).collect();
+/// assert_eq!(v, vec![0, 2, 4, 6, 8, 10, 12, 14]);
+/// ```
+///
+/// With `deterministic(false)`, elements are returned as soon as they are ready instead.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let mut v: Vec<usize> = (0..8).par_map(4, |x| x * 2).deterministic(false).collect();
+/// v.sort();
+/// assert_eq!(v, vec![0, 2, 4, 6, 8, 10, 12, 14]);
+/// ```
+///
+/// If the function panics, the panic is resumed on the consuming thread.
+pub struct ParMap<I, B>
+where
+    I: Iterator,
+{
+    iter: I,
+    f: Arc<dyn Fn(<I as Iterator>::Item) -> B + Send + Sync>,
+    num_workers: usize,
+    deterministic: bool,
+    jobs: Option<Sender<(usize, <I as Iterator>::Item)>>,
+    results: Option<Receiver<(usize, thread::Result<B>)>>,
+    workers: Vec<JoinHandle<()>>,
+    pending: HashMap<usize, B>,
+    sent: usize,
+    returned: usize,
+    exhausted: bool,
+}
+
+impl<I, B> ParMap<I, B>
+where
+    I: Iterator,
+    <I as Iterator>::Item: Send + 'static,
+    B: Send + 'static,
+{
+    pub(crate) fn new(
+        iter: I,
+        num_workers: usize,
+        f: Arc<dyn Fn(<I as Iterator>::Item) -> B + Send + Sync>,
+    ) -> ParMap<I, B> {
+        assert!(
+            num_workers > 0,
+            "par_map: num_workers must be greater than 0"
+        );
+
+        ParMap {
+            iter,
+            f,
+            num_workers,
+            deterministic: true,
+            jobs: None,
+            results: None,
+            workers: Vec::with_capacity(num_workers),
+            pending: HashMap::new(),
+            sent: 0,
+            returned: 0,
+            exhausted: false,
+        }
+    }
+
+    /// when set to false, elements are returned in the order they finish instead of the order of
+    /// the inner iterator.
+    ///
+    /// defaults to true.
+    pub fn deterministic(mut self, deterministic: bool) -> ParMap<I, B> {
+        self.deterministic = deterministic;
+        self
+    }
+
+    fn start(&mut self) {
+        let (job_sender, job_receiver) = mpsc::channel::<(usize, <I as Iterator>::Item)>();
+        let (result_sender, result_receiver) = mpsc::channel();
+        let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+        for _ in 0..self.num_workers {
+            let (jobs, results, f) = (
+                Arc::clone(&job_receiver),
+                result_sender.clone(),
+                Arc::clone(&self.f),
+            );
+
+            self.workers.push(thread::spawn(move || loop {
+                // NOTE: the lock guard is dropped at the end of this statement
+                let job = jobs.lock().unwrap().recv();
+
+                match job {
+                    Ok((index, val)) => {
+                        let result = panic::catch_unwind(AssertUnwindSafe(|| f(val)));
+                        if results.send((index, result)).is_err() {
+                            break;
+                        }
+                    }
+                    Err(_) => break,
+                }
+            }));
+        }
+
+        self.jobs = Some(job_sender);
+        self.results = Some(result_receiver);
+    }
+
+    /// keep up to 2 elements per worker in flight.
+    fn dispatch(&mut self) {
+        while !self.exhausted && self.sent - self.returned < 2 * self.num_workers {
+            match self.iter.next() {
+                Some(val) => {
+                    self.jobs
+                        .as_ref()
+                        .unwrap()
+                        .send((self.sent, val))
+                        .expect("par_map: all workers have stopped");
+                    self.sent += 1;
+                }
+                None => self.exhausted = true,
+            }
+        }
+    }
+
+    fn receive(&mut self) -> (usize, B) {
+        let (index, result) = self
+            .results
+            .as_ref()
+            .unwrap()
+            .recv()
+            .expect("par_map: all workers have stopped");
+
+        match result {
+            Ok(val) => (index, val),
+            Err(err) => panic::resume_unwind(err),
+        }
+    }
+}
+
+impl<I, B> fmt::Debug for ParMap<I, B>
+where
+    I: Iterator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ParMap {{ num_workers: {}, deterministic: {} }}",
+            self.num_workers, self.deterministic
+        )
+    }
+}
+
+impl<I, B> Reiterable for ParMap<I, B>
+where
+    I: Reiterable,
+    <I as Iterator>::Item: Send + 'static,
+    B: Send + 'static,
+{
+    fn reiterate(&self, epoch: usize) -> ParMap<I, B> {
+        ParMap::new(
+            self.iter.reiterate(epoch),
+            self.num_workers,
+            Arc::clone(&self.f),
+        )
+        .deterministic(self.deterministic)
+    }
+}
+
+impl<I, B> Iterator for ParMap<I, B>
+where
+    I: Iterator,
+    <I as Iterator>::Item: Send + 'static,
+    B: Send + 'static,
+{
+    type Item = B;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.jobs.is_none() {
+            self.start();
+        }
+
+        self.dispatch();
+
+        if self.deterministic {
+            loop {
+                if let Some(val) = self.pending.remove(&self.returned) {
+                    self.returned += 1;
+                    return Some(val);
+                }
+
+                if self.returned == self.sent {
+                    return None;
+                }
+
+                let (index, val) = self.receive();
+                self.pending.insert(index, val);
+            }
+        } else if self.returned == self.sent {
+            None
+        } else {
+            let (_, val) = self.receive();
+            self.returned += 1;
+            Some(val)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let in_flight = self.sent - self.returned;
+        cardinality::add_hints(self.iter.size_hint(), (in_flight, Some(in_flight)))
+    }
+}
+
+impl<I, B> ExactSizeIterator for ParMap<I, B>
+where
+    I: ExactSizeIterator,
+    <I as Iterator>::Item: Send + 'static,
+    B: Send + 'static,
+{
+}
+
+impl<I, B> Drop for ParMap<I, B>
+where
+    I: Iterator,
+{
+    fn drop(&mut self) {
+        // dropping the channels makes the workers stop after their current element
+        self.jobs = None;
+        self.results = None;
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
@@ -0,0 +1,172 @@
+use std::fmt;
+use std::panic;
+use std::sync::mpsc::{self, Receiver};
+use std::thread::{self, JoinHandle};
+
+use crate::dataset::cardinality;
+use crate::dataset::Reiterable;
+
+/// Prefetch is an iterator that runs its inner iterator on a background thread, keeping up to
+/// `buffer_size` elements ready ahead of the consumer.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<usize> = (0..8).map(|x| x * 2).prefetch(2).collect();
+/// assert_eq!(v, vec![0, 2, 4, 6, 8, 10, 12, 14]);
+/// ```
+///
+/// The thread is started on the first call to `next`. If the inner iterator panics, the panic
+/// is resumed on the consuming thread. When a Prefetch is dropped, the thread stops after
+/// the element it is currently producing.
+///
+/// ```should_panic
+/// use datasets::Dataset;
+///
+/// let v: Vec<usize> = (0..8)
+///     .map(|x| if x == 4 { panic!("invalid element") } else { x })
+///     .prefetch(2)
+///     .collect();
+/// ```
+///
+/// # Panics
+///
+/// Once the thread is started, the inner iterator belongs to it, so `reiterate` panics on a
+/// Prefetch that has already returned elements. Repeating a dataset reiterates the unstarted
+/// original, so `prefetch(n).repeat(m)` is not affected.
+pub struct Prefetch<I>
+where
+    I: Iterator,
+{
+    iter: Option<I>,
+    buffer_size: usize,
+    receiver: Option<Receiver<<I as Iterator>::Item>>,
+    worker: Option<JoinHandle<()>>,
+    // the size hint of the inner iterator when the thread was started, and the elements
+    // received since
+    started_hint: (usize, Option<usize>),
+    received: usize,
+}
+
+impl<I> Prefetch<I>
+where
+    I: Iterator + Send + 'static,
+    <I as Iterator>::Item: Send + 'static,
+{
+    pub(crate) fn new(iter: I, buffer_size: usize) -> Prefetch<I> {
+        Prefetch {
+            iter: Some(iter),
+            buffer_size,
+            receiver: None,
+            worker: None,
+            started_hint: (0, Some(0)),
+            received: 0,
+        }
+    }
+
+    fn start(&mut self) {
+        if let Some(iter) = self.iter.take() {
+            self.started_hint = iter.size_hint();
+            let (sender, receiver) = mpsc::sync_channel(self.buffer_size);
+
+            let worker = thread::spawn(move || {
+                for val in iter {
+                    // NOTE: an error here means the receiver was dropped, so stop producing
+                    if sender.send(val).is_err() {
+                        break;
+                    }
+                }
+            });
+
+            self.receiver = Some(receiver);
+            self.worker = Some(worker);
+        }
+    }
+}
+
+impl<I> fmt::Debug for Prefetch<I>
+where
+    I: Iterator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Prefetch {{ buffer_size: {}, started: {} }}",
+            self.buffer_size,
+            self.iter.is_none()
+        )
+    }
+}
+
+impl<I> Reiterable for Prefetch<I>
+where
+    I: Reiterable + Send + 'static,
+    <I as Iterator>::Item: Send + 'static,
+{
+    fn reiterate(&self, epoch: usize) -> Prefetch<I> {
+        let iter = self
+            .iter
+            .as_ref()
+            .expect("prefetch: cannot reiterate a started prefetch");
+
+        Prefetch::new(iter.reiterate(epoch), self.buffer_size)
+    }
+}
+
+impl<I> Iterator for Prefetch<I>
+where
+    I: Iterator + Send + 'static,
+    <I as Iterator>::Item: Send + 'static,
+{
+    type Item = <I as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.start();
+
+        let val = self.receiver.as_ref().and_then(|r| r.recv().ok());
+
+        if val.is_some() {
+            self.received += 1;
+        } else {
+            // the worker is done, either because the inner iterator is exhausted or it panicked
+            self.receiver = None;
+            if let Some(worker) = self.worker.take() {
+                if let Err(err) = worker.join() {
+                    panic::resume_unwind(err);
+                }
+            }
+        }
+
+        val
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match (&self.iter, &self.receiver) {
+            (Some(iter), _) => iter.size_hint(),
+            (None, Some(_)) => {
+                cardinality::map_hint(self.started_hint, |n| n.saturating_sub(self.received))
+            }
+            (None, None) => (0, Some(0)),
+        }
+    }
+}
+
+impl<I> ExactSizeIterator for Prefetch<I>
+where
+    I: ExactSizeIterator + Send + 'static,
+    <I as Iterator>::Item: Send + 'static,
+{
+}
+
+impl<I> Drop for Prefetch<I>
+where
+    I: Iterator,
+{
+    fn drop(&mut self) {
+        // dropping the receiver makes the next send on the worker fail
+        self.receiver = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
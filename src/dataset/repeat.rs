@@ -0,0 +1,236 @@
+use std::io::{self, Read, Write};
+
+use crate::dataset::cardinality::{self, INFINITE};
+use crate::dataset::{Cacheable, Checkpoint};
+use crate::Dataset;
+
+/// A Reiterable dataset can start a new pass over its elements.
+///
+/// Every `Clone` iterator is reiterable, a clone taken before iterating starts a new pass.
+/// Adapters like `Shuffle` are reiterable when their inner dataset is, and can use the `epoch`
+/// to behave differently on each pass.
+pub trait Reiterable: Dataset + Sized {
+    /// returns a new pass over the elements of this dataset, for the given epoch.
+    ///
+    /// This is expected to be called on a dataset that has not been iterated yet.
+    fn reiterate(&self, epoch: usize) -> Self;
+}
+
+impl<I> Reiterable for I
+where
+    I: Iterator + Clone,
+{
+    fn reiterate(&self, _epoch: usize) -> I {
+        self.clone()
+    }
+}
+
+/// Repeat is an iterator that returns the elements of a `Reiterable` dataset for a number of
+/// epochs, or forever.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<usize> = (0..3).repeat(2).collect();
+/// assert_eq!(v, vec![0, 1, 2, 0, 1, 2]);
+///
+/// let v: Vec<usize> = (0..3).repeat_forever().take(7).collect();
+/// assert_eq!(v, vec![0, 1, 2, 0, 1, 2, 0]);
+/// ```
+///
+/// Repeating an empty dataset returns nothing, even with `repeat_forever`.
+#[derive(Debug)]
+pub struct Repeat<I>
+where
+    I: Reiterable,
+{
+    source: I,
+    iter: I,
+    epoch: usize,
+    epochs: Option<usize>,
+    empty_epoch: bool,
+}
+
+impl<I> Repeat<I>
+where
+    I: Reiterable,
+{
+    pub(crate) fn new(source: I, epochs: Option<usize>) -> Repeat<I> {
+        Repeat {
+            iter: source.reiterate(0),
+            source,
+            epoch: 0,
+            epochs,
+            empty_epoch: true,
+        }
+    }
+
+    /// the current epoch, starting from 0.
+    pub fn epoch(&self) -> usize {
+        self.epoch
+    }
+
+    /// return the epoch and the index of each element in its epoch along with it.
+    ///
+    /// ```
+    /// use datasets::Dataset;
+    ///
+    /// let v: Vec<(usize, usize, char)> = vec!['a', 'b']
+    ///     .into_iter()
+    ///     .repeat(2)
+    ///     .enumerate_epochs()
+    ///     .collect();
+    ///
+    /// assert_eq!(v, vec![(0, 0, 'a'), (0, 1, 'b'), (1, 0, 'a'), (1, 1, 'b')]);
+    /// ```
+    pub fn enumerate_epochs(self) -> EnumerateEpochs<I> {
+        EnumerateEpochs {
+            epoch: self.epoch,
+            repeat: self,
+            step: 0,
+        }
+    }
+
+    fn finished(&self) -> bool {
+        match self.epochs {
+            Some(epochs) => self.epoch >= epochs,
+            None => false,
+        }
+    }
+}
+
+impl<I> Checkpoint for Repeat<I>
+where
+    I: Reiterable + Checkpoint,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.epoch.write_to(writer)?;
+        self.epochs.write_to(writer)?;
+        self.empty_epoch.write_to(writer)?;
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.epoch = usize::read_from(reader)?;
+        self.epochs = Option::read_from(reader)?;
+        self.empty_epoch = bool::read_from(reader)?;
+        self.iter = self.source.reiterate(self.epoch);
+        self.iter.restore_state(reader)
+    }
+}
+
+impl<I> Iterator for Repeat<I>
+where
+    I: Reiterable,
+{
+    type Item = <I as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.finished() {
+                return None;
+            }
+
+            match self.iter.next() {
+                Some(val) => {
+                    self.empty_epoch = false;
+                    return Some(val);
+                }
+                None => {
+                    if self.empty_epoch {
+                        // NOTE: the dataset is empty, stop instead of looping forever
+                        self.epochs = Some(self.epoch);
+                        return None;
+                    }
+
+                    self.epoch += 1;
+                    if self.finished() {
+                        return None;
+                    }
+
+                    self.iter = self.source.reiterate(self.epoch);
+                    self.empty_epoch = true;
+                }
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.finished() {
+            return (0, Some(0));
+        }
+
+        let pass = self.source.size_hint();
+        match self.epochs {
+            Some(epochs) => {
+                let remaining = epochs - self.epoch - 1;
+                let later = (
+                    pass.0.saturating_mul(remaining),
+                    pass.1.and_then(|n| n.checked_mul(remaining)),
+                );
+
+                cardinality::add_hints(self.iter.size_hint(), later)
+            }
+            None if pass == (0, Some(0)) => self.iter.size_hint(),
+            None if pass.0 > 0 => INFINITE,
+            None => (self.iter.size_hint().0, None),
+        }
+    }
+}
+
+/// EnumerateEpochs is an iterator that returns `(epoch, step, element)` for the elements of a
+/// `Repeat`, where `step` is the index of the element in its epoch.
+#[derive(Debug)]
+pub struct EnumerateEpochs<I>
+where
+    I: Reiterable,
+{
+    repeat: Repeat<I>,
+    epoch: usize,
+    step: usize,
+}
+
+impl<I> Checkpoint for EnumerateEpochs<I>
+where
+    I: Reiterable + Checkpoint,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.epoch.write_to(writer)?;
+        self.step.write_to(writer)?;
+        self.repeat.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.epoch = usize::read_from(reader)?;
+        self.step = usize::read_from(reader)?;
+        self.repeat.restore_state(reader)
+    }
+}
+
+impl<I> Iterator for EnumerateEpochs<I>
+where
+    I: Reiterable,
+{
+    type Item = (usize, usize, <I as Iterator>::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let val = self.repeat.next()?;
+
+        if self.repeat.epoch() != self.epoch {
+            self.epoch = self.repeat.epoch();
+            self.step = 0;
+        }
+
+        self.step += 1;
+        Some((self.epoch, self.step - 1, val))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.repeat.size_hint()
+    }
+}
+
+/// derives the seed for an epoch of a repeated dataset, the first epoch uses `seed` unchanged.
+pub(crate) fn epoch_seed(seed: u64, epoch: usize) -> u64 {
+    seed.wrapping_add((epoch as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15))
+}
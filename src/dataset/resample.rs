@@ -0,0 +1,194 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use rand::Rng;
+
+use crate::dataset::repeat::epoch_seed;
+use crate::dataset::{Cacheable, Checkpoint, CountingRng, Reiterable};
+
+/// RejectionResample is an iterator that drops elements of its inner iterator so that the
+/// classes of the remaining elements follow `target_dist`, like
+/// `tf.data.experimental.rejection_resample`.
+///
+/// An element of class `i` is kept with probability proportional to
+/// `target_dist[i] / initial_dist[i]`, scaled so that the most under-represented class is
+/// always kept. When `initial_dist` is `None`, it is estimated from the classes of the
+/// elements seen so far. Both distributions are normalized, so they can also be given as
+/// counts.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// // 90% of the elements are of class 0
+/// let data = (0..10000).map(|x| if x % 10 == 0 { 1 } else { 0 });
+///
+/// let v: Vec<usize> = data.rejection_resample(|x| *x, vec![0.5, 0.5], None, 42).collect();
+/// let ones = v.iter().filter(|x| **x == 1).count();
+///
+/// assert_eq!(ones, 1000);
+/// assert!((v.len() as f64 - 2000.0).abs() < 100.0);
+/// ```
+///
+/// This will panic if `class_fn` returns a class that is out of bounds for `target_dist`, or
+/// `initial_dist` has a different number of classes.
+pub struct RejectionResample<I, F>
+where
+    I: Iterator,
+{
+    iter: I,
+    class_fn: F,
+    target_dist: Vec<f64>,
+    initial_dist: Option<Vec<f64>>,
+    counts: Vec<u64>,
+    seed: u64,
+    rng: CountingRng,
+}
+
+impl<I, F> RejectionResample<I, F>
+where
+    I: Iterator,
+    F: FnMut(&<I as Iterator>::Item) -> usize,
+{
+    pub(crate) fn new(
+        iter: I,
+        class_fn: F,
+        target_dist: Vec<f64>,
+        initial_dist: Option<Vec<f64>>,
+        seed: u64,
+    ) -> RejectionResample<I, F> {
+        if let Some(ref initial_dist) = initial_dist {
+            assert_eq!(
+                initial_dist.len(),
+                target_dist.len(),
+                "rejection_resample: expected the same number of classes in the target and initial distributions"
+            );
+        }
+
+        RejectionResample {
+            iter,
+            class_fn,
+            counts: vec![0; target_dist.len()],
+            target_dist: normalize(target_dist),
+            initial_dist: initial_dist.map(normalize),
+            seed,
+            rng: CountingRng::new(seed),
+        }
+    }
+
+    /// the probability of keeping an element of `class`.
+    fn acceptance(&self, class: usize) -> f64 {
+        let total: u64 = self.counts.iter().sum();
+
+        let initial = |i: usize| match self.initial_dist {
+            Some(ref dist) => dist[i],
+            None => self.counts[i] as f64 / total as f64,
+        };
+
+        let ratio = |i: usize| {
+            let initial = initial(i);
+            if initial > 0.0 {
+                self.target_dist[i] / initial
+            } else {
+                0.0
+            }
+        };
+
+        let max_ratio = (0..self.target_dist.len()).map(ratio).fold(0.0, f64::max);
+
+        if max_ratio > 0.0 {
+            ratio(class) / max_ratio
+        } else {
+            0.0
+        }
+    }
+}
+
+fn normalize(dist: Vec<f64>) -> Vec<f64> {
+    let total: f64 = dist.iter().sum();
+    assert!(
+        total > 0.0 && dist.iter().all(|p| *p >= 0.0),
+        "rejection_resample: distributions must be non-negative and not all 0"
+    );
+
+    dist.into_iter().map(|p| p / total).collect()
+}
+
+impl<I, F> fmt::Debug for RejectionResample<I, F>
+where
+    I: Iterator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "RejectionResample {{ target_dist: {:?}, initial_dist: {:?}, counts: {:?} }}",
+            self.target_dist, self.initial_dist, self.counts
+        )
+    }
+}
+
+impl<I, F> Reiterable for RejectionResample<I, F>
+where
+    I: Reiterable,
+    F: FnMut(&<I as Iterator>::Item) -> usize + Clone,
+{
+    fn reiterate(&self, epoch: usize) -> RejectionResample<I, F> {
+        let mut resample = RejectionResample::new(
+            self.iter.reiterate(epoch),
+            self.class_fn.clone(),
+            self.target_dist.clone(),
+            self.initial_dist.clone(),
+            self.seed,
+        );
+
+        resample.rng = CountingRng::new(epoch_seed(self.seed, epoch));
+        resample
+    }
+}
+
+impl<I, F> Checkpoint for RejectionResample<I, F>
+where
+    I: Iterator + Checkpoint,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.counts.write_to(writer)?;
+        self.rng.write_to(writer)?;
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.counts = Vec::read_from(reader)?;
+        self.rng = CountingRng::read_from(reader)?;
+        self.iter.restore_state(reader)
+    }
+}
+
+impl<I, F> Iterator for RejectionResample<I, F>
+where
+    I: Iterator,
+    F: FnMut(&<I as Iterator>::Item) -> usize,
+{
+    type Item = <I as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let val = self.iter.next()?;
+            let class = (self.class_fn)(&val);
+            assert!(
+                class < self.target_dist.len(),
+                "rejection_resample: class {} is out of bounds for {} classes",
+                class,
+                self.target_dist.len()
+            );
+
+            self.counts[class] += 1;
+
+            if self.rng.gen::<f64>() < self.acceptance(class) {
+                return Some(val);
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
@@ -0,0 +1,400 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use rand::Rng;
+
+use crate::dataset::cardinality;
+use crate::dataset::checkpoint::invalid_state;
+use crate::dataset::repeat::epoch_seed;
+use crate::dataset::{Cacheable, Checkpoint, CountingRng, Reiterable};
+use crate::Dataset;
+
+/// sample_from_datasets returns a dataset that randomly picks its next element from one of
+/// `datasets`, with probabilities proportional to `weights`.
+///
+/// With `stop_on_empty_dataset`, the result ends as soon as any of the datasets is exhausted.
+/// Otherwise, exhausted datasets are removed and sampling continues from the rest, until all of
+/// them are exhausted.
+///
+/// ```
+/// use datasets::{sample_from_datasets, Dataset};
+///
+/// let spam = vec!["spam"; 10].into_iter();
+/// let ham = vec!["ham"; 10].into_iter();
+///
+/// let v: Vec<&str> = sample_from_datasets(vec![spam, ham], vec![0.8, 0.2], 42, false).collect();
+/// assert_eq!(v.len(), 20);
+///
+/// let v: Vec<&str> = sample_from_datasets(
+///     vec![vec!["spam"; 10].into_iter(), vec!["ham"; 10].into_iter()],
+///     vec![0.8, 0.2],
+///     42,
+///     true,
+/// )
+/// .collect();
+/// assert!(v.len() < 20);
+/// ```
+///
+/// To sample from datasets of different types, box them first, as
+/// `Box<dyn Iterator<Item = T>>`.
+///
+/// This will panic if the number of weights does not match the number of datasets, or if a
+/// weight is negative.
+pub fn sample_from_datasets<D>(
+    datasets: Vec<D>,
+    weights: Vec<f64>,
+    seed: u64,
+    stop_on_empty_dataset: bool,
+) -> SampleFromDatasets<D>
+where
+    D: Dataset,
+{
+    assert_eq!(
+        datasets.len(),
+        weights.len(),
+        "sample_from_datasets: expected one weight per dataset"
+    );
+    assert!(
+        weights.iter().all(|w| *w >= 0.0),
+        "sample_from_datasets: weights cannot be negative"
+    );
+
+    SampleFromDatasets {
+        datasets: datasets.into_iter().map(Some).collect(),
+        weights,
+        seed,
+        stop_on_empty_dataset,
+        rng: CountingRng::new(seed),
+        stopped: false,
+    }
+}
+
+/// SampleFromDatasets is the iterator returned by `sample_from_datasets`.
+pub struct SampleFromDatasets<D>
+where
+    D: Dataset,
+{
+    datasets: Vec<Option<D>>,
+    weights: Vec<f64>,
+    seed: u64,
+    stop_on_empty_dataset: bool,
+    rng: CountingRng,
+    stopped: bool,
+}
+
+impl<D> fmt::Debug for SampleFromDatasets<D>
+where
+    D: Dataset,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "SampleFromDatasets {{ weights: {:?}, stop_on_empty_dataset: {}, rng: {:?} }}",
+            self.weights, self.stop_on_empty_dataset, self.rng
+        )
+    }
+}
+
+impl<D> Reiterable for SampleFromDatasets<D>
+where
+    D: Reiterable,
+{
+    fn reiterate(&self, epoch: usize) -> SampleFromDatasets<D> {
+        let datasets = self
+            .datasets
+            .iter()
+            .map(|d| d.as_ref().map(|d| d.reiterate(epoch)))
+            .collect();
+
+        SampleFromDatasets {
+            datasets,
+            weights: self.weights.clone(),
+            seed: self.seed,
+            stop_on_empty_dataset: self.stop_on_empty_dataset,
+            rng: CountingRng::new(epoch_seed(self.seed, epoch)),
+            stopped: false,
+        }
+    }
+}
+
+impl<D> Checkpoint for SampleFromDatasets<D>
+where
+    D: Dataset + Checkpoint,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.stopped.write_to(writer)?;
+        self.rng.write_to(writer)?;
+        save_datasets(&self.datasets, writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.stopped = bool::read_from(reader)?;
+        self.rng = CountingRng::read_from(reader)?;
+        restore_datasets(&mut self.datasets, reader)
+    }
+}
+
+impl<D> Iterator for SampleFromDatasets<D>
+where
+    D: Dataset,
+{
+    type Item = <D as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.stopped {
+            let total: f64 = self
+                .datasets
+                .iter()
+                .zip(&self.weights)
+                .filter(|(d, _)| d.is_some())
+                .map(|(_, w)| w)
+                .sum();
+
+            if total <= 0.0 {
+                self.stopped = true;
+                break;
+            }
+
+            let mut target = self.rng.gen_range(0.0, total);
+            let mut index = None;
+            for (i, (d, w)) in self.datasets.iter().zip(&self.weights).enumerate() {
+                if d.is_some() && *w > 0.0 {
+                    index = Some(i);
+                    if target < *w {
+                        break;
+                    }
+
+                    target -= w;
+                }
+            }
+
+            // NOTE: index is always set here, since total is positive
+            let index = index.unwrap();
+            match self.datasets[index].as_mut().and_then(|d| d.next()) {
+                Some(val) => return Some(val),
+                None => {
+                    self.datasets[index] = None;
+                    self.stopped = self.stop_on_empty_dataset;
+                }
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.stopped {
+            return (0, Some(0));
+        }
+
+        // datasets with a weight of 0 are never sampled
+        let hints: Vec<_> = self
+            .datasets
+            .iter()
+            .zip(&self.weights)
+            .filter(|(_, w)| **w > 0.0)
+            .filter_map(|(d, _)| d.as_ref().map(Iterator::size_hint))
+            .collect();
+
+        let (lower, upper) = hints
+            .iter()
+            .cloned()
+            .fold((0, Some(0)), cardinality::add_hints);
+
+        if self.stop_on_empty_dataset {
+            // at least the elements of the shortest dataset are returned before it is empty
+            let shortest = hints.iter().map(|(lower, _)| *lower).min().unwrap_or(0);
+            (shortest, upper)
+        } else {
+            (lower, upper)
+        }
+    }
+}
+
+/// choose_from_datasets returns a dataset that takes its next element from the dataset at the
+/// index returned by `selector`.
+///
+/// With `stop_on_empty_dataset`, the result ends when the selected dataset is exhausted.
+/// Otherwise, selections of exhausted datasets are skipped. The result always ends when
+/// `selector` does.
+///
+/// ```
+/// use datasets::{choose_from_datasets, Dataset};
+///
+/// let datasets = vec![vec!["a"; 3].into_iter(), vec!["b"; 3].into_iter()];
+/// let selector = vec![0, 1, 1, 0].into_iter().cycle();
+///
+/// let v: Vec<&str> = choose_from_datasets(datasets, selector, false).collect();
+/// assert_eq!(v, vec!["a", "b", "b", "a", "a", "b"]);
+/// ```
+///
+/// This will panic if `selector` returns an index that is out of bounds.
+pub fn choose_from_datasets<D, S>(
+    datasets: Vec<D>,
+    selector: S,
+    stop_on_empty_dataset: bool,
+) -> ChooseFromDatasets<D, S>
+where
+    D: Dataset,
+    S: Iterator<Item = usize>,
+{
+    ChooseFromDatasets {
+        remaining: datasets.len(),
+        datasets: datasets.into_iter().map(Some).collect(),
+        selector,
+        stop_on_empty_dataset,
+    }
+}
+
+/// ChooseFromDatasets is the iterator returned by `choose_from_datasets`.
+pub struct ChooseFromDatasets<D, S>
+where
+    D: Dataset,
+    S: Iterator<Item = usize>,
+{
+    datasets: Vec<Option<D>>,
+    selector: S,
+    stop_on_empty_dataset: bool,
+    remaining: usize,
+}
+
+impl<D, S> fmt::Debug for ChooseFromDatasets<D, S>
+where
+    D: Dataset,
+    S: Iterator<Item = usize>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ChooseFromDatasets {{ remaining: {}, stop_on_empty_dataset: {} }}",
+            self.remaining, self.stop_on_empty_dataset
+        )
+    }
+}
+
+impl<D, S> Reiterable for ChooseFromDatasets<D, S>
+where
+    D: Reiterable,
+    S: Reiterable<Item = usize>,
+{
+    fn reiterate(&self, epoch: usize) -> ChooseFromDatasets<D, S> {
+        ChooseFromDatasets {
+            datasets: self
+                .datasets
+                .iter()
+                .map(|d| d.as_ref().map(|d| d.reiterate(epoch)))
+                .collect(),
+            selector: self.selector.reiterate(epoch),
+            stop_on_empty_dataset: self.stop_on_empty_dataset,
+            remaining: self.remaining,
+        }
+    }
+}
+
+impl<D, S> Checkpoint for ChooseFromDatasets<D, S>
+where
+    D: Dataset + Checkpoint,
+    S: Iterator<Item = usize> + Checkpoint,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.remaining.write_to(writer)?;
+        self.selector.save_state(writer)?;
+        save_datasets(&self.datasets, writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.remaining = usize::read_from(reader)?;
+        self.selector.restore_state(reader)?;
+        restore_datasets(&mut self.datasets, reader)
+    }
+}
+
+impl<D, S> Iterator for ChooseFromDatasets<D, S>
+where
+    D: Dataset,
+    S: Iterator<Item = usize>,
+{
+    type Item = <D as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.remaining > 0 {
+            let index = self.selector.next()?;
+            let dataset = &mut self.datasets[index];
+
+            match dataset.as_mut().and_then(|d| d.next()) {
+                Some(val) => return Some(val),
+                None => {
+                    if dataset.take().is_some() {
+                        self.remaining -= 1;
+                    }
+
+                    if self.stop_on_empty_dataset {
+                        self.remaining = 0;
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.remaining == 0 {
+            return (0, Some(0));
+        }
+
+        let (_, datasets) = self
+            .datasets
+            .iter()
+            .flatten()
+            .map(Iterator::size_hint)
+            .fold((0, Some(0)), cardinality::add_hints);
+
+        let upper = match (datasets, self.selector.size_hint().1) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        (0, upper)
+    }
+}
+
+// saves which of `datasets` are not exhausted yet, followed by their states
+fn save_datasets<D>(datasets: &[Option<D>], writer: &mut dyn Write) -> io::Result<()>
+where
+    D: Checkpoint,
+{
+    datasets.len().write_to(writer)?;
+    for d in datasets {
+        d.is_some().write_to(writer)?;
+    }
+
+    for d in datasets.iter().flatten() {
+        d.save_state(writer)?;
+    }
+
+    Ok(())
+}
+
+fn restore_datasets<D>(datasets: &mut [Option<D>], reader: &mut dyn Read) -> io::Result<()>
+where
+    D: Checkpoint,
+{
+    if usize::read_from(reader)? != datasets.len() {
+        return Err(invalid_state("wrong number of datasets"));
+    }
+
+    for d in datasets.iter_mut() {
+        if !bool::read_from(reader)? {
+            *d = None;
+        } else if d.is_none() {
+            return Err(invalid_state("dataset was already exhausted"));
+        }
+    }
+
+    for d in datasets.iter_mut().flatten() {
+        d.restore_state(reader)?;
+    }
+
+    Ok(())
+}
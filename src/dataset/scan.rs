@@ -0,0 +1,117 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::dataset::{Cacheable, Checkpoint, Reiterable};
+
+/// StatefulScan is an iterator that applies a function to the elements of its inner iterator
+/// along with a mutable state, like `tf.data.Dataset.scan`.
+///
+/// Unlike `Iterator::scan`, the state is reset to its initial value at the start of every
+/// epoch of a repeated dataset, and is saved along with the position of the inner iterator in
+/// a checkpoint.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// // the running mean of the elements in each epoch
+/// let v: Vec<f64> = vec![2.0, 4.0, 6.0]
+///     .into_iter()
+///     .stateful_scan((0.0, 0), |(sum, count), x| {
+///         *sum += x;
+///         *count += 1;
+///         *sum / *count as f64
+///     })
+///     .repeat(2)
+///     .collect();
+///
+/// assert_eq!(v, vec![2.0, 3.0, 4.0, 2.0, 3.0, 4.0]);
+/// ```
+pub struct StatefulScan<I, S, F> {
+    iter: I,
+    initial_state: S,
+    state: S,
+    f: F,
+}
+
+impl<I, S, F> StatefulScan<I, S, F>
+where
+    S: Clone,
+{
+    pub(crate) fn new(iter: I, initial_state: S, f: F) -> StatefulScan<I, S, F> {
+        StatefulScan {
+            iter,
+            state: initial_state.clone(),
+            initial_state,
+            f,
+        }
+    }
+
+    /// the current state.
+    pub fn state(&self) -> &S {
+        &self.state
+    }
+}
+
+impl<I, S, F> fmt::Debug for StatefulScan<I, S, F>
+where
+    S: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "StatefulScan {{ state: {:?} }}", self.state)
+    }
+}
+
+impl<I, S, F, B> Reiterable for StatefulScan<I, S, F>
+where
+    I: Reiterable,
+    S: Clone,
+    F: FnMut(&mut S, <I as Iterator>::Item) -> B + Clone,
+{
+    fn reiterate(&self, epoch: usize) -> StatefulScan<I, S, F> {
+        StatefulScan::new(
+            self.iter.reiterate(epoch),
+            self.initial_state.clone(),
+            self.f.clone(),
+        )
+    }
+}
+
+impl<I, S, F> Checkpoint for StatefulScan<I, S, F>
+where
+    I: Checkpoint,
+    S: Cacheable,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.state.write_to(writer)?;
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.state = S::read_from(reader)?;
+        self.iter.restore_state(reader)
+    }
+}
+
+impl<I, S, F, B> Iterator for StatefulScan<I, S, F>
+where
+    I: Iterator,
+    F: FnMut(&mut S, <I as Iterator>::Item) -> B,
+{
+    type Item = B;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let val = self.iter.next()?;
+        Some((self.f)(&mut self.state, val))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<I, S, F, B> ExactSizeIterator for StatefulScan<I, S, F>
+where
+    I: ExactSizeIterator,
+    F: FnMut(&mut S, <I as Iterator>::Item) -> B,
+{
+}
@@ -0,0 +1,104 @@
+use std::io::{self, Read, Write};
+
+use crate::dataset::cardinality;
+use crate::dataset::{Cacheable, Checkpoint, Reiterable};
+
+/// Shard is an iterator that returns every `num_shards`th element of its inner iterator,
+/// starting from `index`, so that `num_shards` workers each see a disjoint part of a dataset.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<usize> = (0..10).shard(3, 1).collect();
+/// assert_eq!(v, vec![1, 4, 7]);
+/// ```
+///
+/// Elements are skipped with `Iterator::nth`, so datasets that support random access, like
+/// the mnist datasets, seek directly to the elements of their shard instead of reading the
+/// others.
+#[derive(Debug)]
+pub struct Shard<I>
+where
+    I: Iterator,
+{
+    iter: I,
+    num_shards: usize,
+    index: usize,
+    started: bool,
+}
+
+impl<I> Shard<I>
+where
+    I: Iterator,
+{
+    pub(crate) fn new(iter: I, num_shards: usize, index: usize) -> Shard<I> {
+        assert!(num_shards > 0, "shard: num_shards must be greater than 0");
+        assert!(
+            index < num_shards,
+            "shard: index {} must be less than num_shards {}",
+            index,
+            num_shards
+        );
+
+        Shard {
+            iter,
+            num_shards,
+            index,
+            started: false,
+        }
+    }
+}
+
+impl<I> Reiterable for Shard<I>
+where
+    I: Reiterable,
+{
+    fn reiterate(&self, epoch: usize) -> Shard<I> {
+        Shard::new(self.iter.reiterate(epoch), self.num_shards, self.index)
+    }
+}
+
+impl<I> Checkpoint for Shard<I>
+where
+    I: Iterator + Checkpoint,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.started.write_to(writer)?;
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.started = bool::read_from(reader)?;
+        self.iter.restore_state(reader)
+    }
+}
+
+impl<I> Iterator for Shard<I>
+where
+    I: Iterator,
+{
+    type Item = <I as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.started {
+            self.iter.nth(self.num_shards - 1)
+        } else {
+            self.started = true;
+            self.iter.nth(self.index)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        cardinality::map_hint(self.iter.size_hint(), |n| {
+            if self.started {
+                n / self.num_shards
+            } else if n > self.index {
+                (n - self.index - 1) / self.num_shards + 1
+            } else {
+                0
+            }
+        })
+    }
+}
+
+impl<I> ExactSizeIterator for Shard<I> where I: ExactSizeIterator {}
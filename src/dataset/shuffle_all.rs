@@ -0,0 +1,197 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+use crate::dataset::checkpoint::invalid_state;
+use crate::dataset::repeat::epoch_seed;
+use crate::dataset::{Cacheable, Checkpoint, Reiterable};
+
+/// ShuffleAll is an iterator that returns all elements of a finite dataset in a uniformly
+/// random order.
+///
+/// Unlike `shuffle`, which only shuffles within a buffer, this collects the entire dataset and
+/// draws a Fisher–Yates permutation of its indices, one index per call to `next`.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<usize> = (0..8).shuffle_all(0).collect();
+/// assert_eq!(v.len(), 8);
+///
+/// let mut sorted = v.clone();
+/// sorted.sort();
+/// assert_eq!(sorted, (0..8).collect::<Vec<usize>>());
+///
+/// // the same seed always returns the same order
+/// assert_eq!(v, (0..8).shuffle_all(0).collect::<Vec<usize>>());
+/// ```
+///
+/// When repeated, every epoch is shuffled with a different seed derived from `seed`, unless
+/// `reshuffle_each_iteration` is set to false.
+///
+/// Sources with random access should use `IndexedDataset::permuted` instead, which stays lazy:
+/// the mnist datasets then read each image from disk when it is returned, instead of loading all
+/// of them into memory first.
+pub struct ShuffleAll<T> {
+    data: Vec<Option<T>>,
+    permutation: Permutation,
+    seed: u64,
+    reshuffle_each_iteration: bool,
+}
+
+impl<T> ShuffleAll<T> {
+    pub(crate) fn new(data: Vec<T>, seed: u64) -> ShuffleAll<T> {
+        ShuffleAll {
+            permutation: Permutation::new(data.len(), seed),
+            data: data.into_iter().map(Some).collect(),
+            seed,
+            reshuffle_each_iteration: true,
+        }
+    }
+
+    /// when set to false, every epoch of a repeated shuffle returns elements in the same order.
+    ///
+    /// defaults to true.
+    pub fn reshuffle_each_iteration(mut self, reshuffle_each_iteration: bool) -> ShuffleAll<T> {
+        self.reshuffle_each_iteration = reshuffle_each_iteration;
+        self
+    }
+}
+
+impl<T> fmt::Debug for ShuffleAll<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ShuffleAll {{ len: {}, permutation: {:?} }}",
+            self.data.len(),
+            self.permutation
+        )
+    }
+}
+
+impl<T> Reiterable for ShuffleAll<T>
+where
+    T: Clone,
+{
+    fn reiterate(&self, epoch: usize) -> ShuffleAll<T> {
+        let seed = if self.reshuffle_each_iteration {
+            epoch_seed(self.seed, epoch)
+        } else {
+            self.seed
+        };
+
+        ShuffleAll {
+            data: self.data.clone(),
+            permutation: Permutation::new(self.data.len(), seed),
+            seed: self.seed,
+            reshuffle_each_iteration: self.reshuffle_each_iteration,
+        }
+    }
+}
+
+impl<T> Checkpoint for ShuffleAll<T> {
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.permutation.position.write_to(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        // NOTE: the permutation is drawn again up to the saved position, so the elements do not
+        // need to be saved
+        let position = usize::read_from(reader)?;
+        if position < self.permutation.position || position > self.data.len() {
+            return Err(invalid_state("shuffle_all position out of range"));
+        }
+
+        while self.permutation.position < position {
+            self.next();
+        }
+
+        Ok(())
+    }
+}
+
+impl<T> Iterator for ShuffleAll<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let index = self.permutation.next()?;
+        self.data[index].take()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.permutation.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for ShuffleAll<T> {}
+
+/// Permutation is an iterator over a uniformly random permutation of `0..len`, drawn one index
+/// at a time with the Fisher–Yates shuffle.
+///
+/// `shuffle_all`, `permuted`, `split` and `kfold` all draw their permutations with it.
+pub(crate) struct Permutation<R = XorShiftRng> {
+    indices: Vec<usize>,
+    position: usize,
+    rng: R,
+}
+
+impl Permutation {
+    pub(crate) fn new(len: usize, seed: u64) -> Permutation {
+        Permutation::with_rng(len, XorShiftRng::seed_from_u64(seed))
+    }
+}
+
+impl<R> Permutation<R> {
+    /// creates a permutation drawn with `rng`, which may be a `&mut` to share it with others.
+    pub(crate) fn with_rng(len: usize, rng: R) -> Permutation<R> {
+        Permutation {
+            indices: (0..len).collect(),
+            position: 0,
+            rng,
+        }
+    }
+
+    /// the number of indices returned so far.
+    pub(crate) fn position(&self) -> usize {
+        self.position
+    }
+}
+
+impl<R> fmt::Debug for Permutation<R>
+where
+    R: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Permutation {{ position: {}, rng: {:?} }}",
+            self.position, self.rng
+        )
+    }
+}
+
+impl<R> Iterator for Permutation<R>
+where
+    R: Rng,
+{
+    type Item = usize;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.position == self.indices.len() {
+            return None;
+        }
+
+        let swap = self.rng.gen_range(self.position, self.indices.len());
+        self.indices.swap(self.position, swap);
+        self.position += 1;
+
+        Some(self.indices[self.position - 1])
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.indices.len() - self.position;
+        (n, Some(n))
+    }
+}
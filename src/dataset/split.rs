@@ -0,0 +1,518 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+use std::io::{self, Read, Write};
+use std::ops::Range;
+use std::str::FromStr;
+use std::vec;
+
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+use crate::dataset::cardinality;
+use crate::dataset::shuffle_all::Permutation;
+use crate::dataset::{Cacheable, Checkpoint, Reiterable};
+
+/// returns the indices `0..n` in a random order, drawn with a `Permutation`.
+pub(crate) fn permutation<R>(n: usize, rng: &mut R) -> Vec<usize>
+where
+    R: Rng,
+{
+    Permutation::with_rng(n, rng).collect()
+}
+
+/// randomly splits `v` into two parts, with `count` elements in the first.
+pub(crate) fn split_vec<T>(v: Vec<T>, count: usize, rng: &mut XorShiftRng) -> (Vec<T>, Vec<T>) {
+    let indices = permutation(v.len(), rng);
+    let mut v: Vec<Option<T>> = v.into_iter().map(Some).collect();
+
+    let mut first = Vec::with_capacity(count);
+    let mut second = Vec::with_capacity(v.len().saturating_sub(count));
+
+    for (i, index) in indices.into_iter().enumerate() {
+        let val = v[index].take().unwrap();
+        if i < count {
+            first.push(val);
+        } else {
+            second.push(val);
+        }
+    }
+
+    (first, second)
+}
+
+/// the number of elements in the first part when splitting `n` elements at `fraction`.
+pub(crate) fn fraction_count(n: usize, fraction: f64) -> usize {
+    assert!(
+        (0.0..=1.0).contains(&fraction),
+        "split: fraction must be between 0 and 1"
+    );
+
+    (n as f64 * fraction).round() as usize
+}
+
+/// Split is a selection of the splits of a dataset, written as in tensorflow datasets.
+///
+/// - `"train"`: the entire train split
+/// - `"train[:90%]"`, `"train[90%:]"`: the first 90%, and the last 10% of the train split
+/// - `"train[1000:2000]"`: elements 1000 to 2000 of the train split
+/// - `"train[-10%:]"`: negative bounds count from the end
+/// - `"train[:10%]+test"`: parts are concatenated with `+`
+///
+/// Percentages are rounded to the closest element.
+///
+/// ```
+/// use datasets::Split;
+///
+/// let split: Split = "train[:90%]+test[-1000:]".parse().unwrap();
+/// assert_eq!(split.parts().len(), 2);
+///
+/// assert_eq!(split.parts()[0].name(), "train");
+/// assert_eq!(split.parts()[0].range(60000), 0..54000);
+///
+/// assert_eq!(split.parts()[1].name(), "test");
+/// assert_eq!(split.parts()[1].range(10000), 9000..10000);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Split {
+    parts: Vec<SplitPart>,
+}
+
+/// SplitPart is a single named split in a `Split`, with an optional slice.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitPart {
+    name: String,
+    start: Option<Bound>,
+    end: Option<Bound>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Bound {
+    Absolute(i64),
+    Percent(i64),
+}
+
+impl Split {
+    /// the parts of this split, in order.
+    pub fn parts(&self) -> &[SplitPart] {
+        &self.parts
+    }
+
+    /// creates a dataset with the elements of each part of this split, in order.
+    ///
+    /// `load` is called with the name of every part, and should return a new dataset with all
+    /// the elements of the split with that name, along with its length.
+    pub fn load<D, F>(&self, mut load: F) -> Result<SplitDataset<D>, Box<dyn Error>>
+    where
+        D: Iterator,
+        F: FnMut(&str) -> Result<(D, usize), Box<dyn Error>>,
+    {
+        let mut parts = Vec::with_capacity(self.parts.len());
+        for part in &self.parts {
+            let (dataset, len) = load(&part.name)?;
+            parts.push((dataset, part.range(len)));
+        }
+
+        Ok(SplitDataset {
+            parts,
+            current: 0,
+            position: 0,
+        })
+    }
+}
+
+impl SplitPart {
+    /// the name of the split.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// the range of elements selected from a split with `len` elements.
+    pub fn range(&self, len: usize) -> Range<usize> {
+        let resolve = |bound: Option<Bound>, default: usize| {
+            let value = match bound {
+                None => return default,
+                Some(Bound::Absolute(value)) => value,
+                Some(Bound::Percent(value)) => (len as f64 * value as f64 / 100.0).round() as i64,
+            };
+
+            let value = if value < 0 { len as i64 + value } else { value };
+
+            value.max(0).min(len as i64) as usize
+        };
+
+        let start = resolve(self.start, 0);
+        let end = resolve(self.end, len);
+        start..end.max(start)
+    }
+}
+
+impl FromStr for Split {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<Split, Box<dyn Error>> {
+        let parts = s
+            .split('+')
+            .map(|part| part.trim().parse())
+            .collect::<Result<Vec<SplitPart>, Box<dyn Error>>>()?;
+
+        Ok(Split { parts })
+    }
+}
+
+impl FromStr for SplitPart {
+    type Err = Box<dyn Error>;
+
+    fn from_str(s: &str) -> Result<SplitPart, Box<dyn Error>> {
+        let invalid = || format!("invalid split: {:?}", s);
+
+        let (name, slice) = match s.find('[') {
+            Some(idx) => {
+                if !s.ends_with(']') {
+                    return Err(invalid().into());
+                }
+
+                (&s[..idx], Some(&s[idx + 1..s.len() - 1]))
+            }
+            None => (s, None),
+        };
+
+        if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+            return Err(invalid().into());
+        }
+
+        let (start, end) = match slice {
+            Some(slice) => {
+                let mut bounds = slice.split(':');
+                match (bounds.next(), bounds.next(), bounds.next()) {
+                    (Some(start), Some(end), None) => (
+                        parse_bound(start).ok_or_else(invalid)?,
+                        parse_bound(end).ok_or_else(invalid)?,
+                    ),
+                    _ => return Err(invalid().into()),
+                }
+            }
+            None => (None, None),
+        };
+
+        Ok(SplitPart {
+            name: name.to_string(),
+            start,
+            end,
+        })
+    }
+}
+
+/// parses a single bound of a slice, returning `Some(None)` for an empty bound.
+fn parse_bound(s: &str) -> Option<Option<Bound>> {
+    let s = s.trim();
+    if s.is_empty() {
+        Some(None)
+    } else if let Some(s) = s.strip_suffix('%') {
+        let value: i64 = s.trim().parse().ok()?;
+        if value.abs() > 100 {
+            None
+        } else {
+            Some(Some(Bound::Percent(value)))
+        }
+    } else {
+        s.parse().ok().map(|value| Some(Bound::Absolute(value)))
+    }
+}
+
+impl fmt::Display for Split {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, part) in self.parts.iter().enumerate() {
+            if i > 0 {
+                write!(f, "+")?;
+            }
+
+            write!(f, "{}", part)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for SplitPart {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+
+        if self.start.is_some() || self.end.is_some() {
+            let bound = |b: Option<Bound>| match b {
+                None => String::new(),
+                Some(Bound::Absolute(value)) => value.to_string(),
+                Some(Bound::Percent(value)) => format!("{}%", value),
+            };
+
+            write!(f, "[{}:{}]", bound(self.start), bound(self.end))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// SplitDataset is an iterator over the parts of a `Split`, returned by `Split::load`.
+///
+/// Elements before the start of a part are skipped with `Iterator::nth`, and empty parts yield
+/// nothing.
+///
+/// ```
+/// use datasets::Split;
+///
+/// let load = |_: &str| Ok((0..10, 10));
+///
+/// let split: Split = "train[2:4]+train[3:1]+train[5:5]+train[50%:50%]".parse().unwrap();
+/// let v: Vec<i32> = split.load(load).unwrap().collect();
+/// assert_eq!(v, vec![2, 3]);
+/// ```
+#[derive(Debug)]
+pub struct SplitDataset<D> {
+    parts: Vec<(D, Range<usize>)>,
+    current: usize,
+    position: usize,
+}
+
+impl<D> Reiterable for SplitDataset<D>
+where
+    D: Reiterable,
+{
+    fn reiterate(&self, epoch: usize) -> SplitDataset<D> {
+        SplitDataset {
+            parts: self
+                .parts
+                .iter()
+                .map(|(d, r)| (d.reiterate(epoch), r.clone()))
+                .collect(),
+            current: 0,
+            position: 0,
+        }
+    }
+}
+
+impl<D> Checkpoint for SplitDataset<D>
+where
+    D: Iterator + Checkpoint,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.current.write_to(writer)?;
+        self.position.write_to(writer)?;
+        match self.parts.get(self.current) {
+            Some((d, _)) => d.save_state(writer),
+            None => Ok(()),
+        }
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.current = usize::read_from(reader)?;
+        self.position = usize::read_from(reader)?;
+        match self.parts.get_mut(self.current) {
+            Some((d, _)) => d.restore_state(reader),
+            None => Ok(()),
+        }
+    }
+}
+
+impl<D> Iterator for SplitDataset<D>
+where
+    D: Iterator,
+{
+    type Item = <D as Iterator>::Item;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((dataset, range)) = self.parts.get_mut(self.current) {
+            if range.start < range.end && self.position < range.end {
+                let val = if self.position < range.start {
+                    let val = dataset.nth(range.start - self.position);
+                    self.position = range.start + 1;
+                    val
+                } else {
+                    self.position += 1;
+                    dataset.next()
+                };
+
+                if val.is_some() {
+                    return val;
+                }
+            }
+
+            self.current += 1;
+            self.position = 0;
+        }
+
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.parts
+            .iter()
+            .enumerate()
+            .skip(self.current)
+            .map(|(i, (dataset, range))| {
+                let position = if i == self.current { self.position } else { 0 };
+                let start = range.start.max(position);
+                let wanted = range.end.saturating_sub(start);
+
+                // the part ends early if its dataset does
+                let available = |n: usize| n.saturating_sub(start - position).min(wanted);
+                let (lower, upper) = dataset.size_hint();
+                (available(lower), Some(upper.map_or(wanted, available)))
+            })
+            .fold((0, Some(0)), cardinality::add_hints)
+    }
+}
+
+/// randomly splits `v` into two parts, with `fraction` of the elements with every label in
+/// the first.
+pub(crate) fn stratified_split_vec<T, K, F>(
+    v: Vec<T>,
+    fraction: f64,
+    mut label_fn: F,
+    rng: &mut XorShiftRng,
+) -> (Vec<T>, Vec<T>)
+where
+    K: Eq + Hash,
+    F: FnMut(&T) -> K,
+{
+    let mut groups: Vec<Vec<T>> = Vec::new();
+    let mut index: HashMap<K, usize> = HashMap::new();
+
+    for val in v {
+        let label = label_fn(&val);
+        let i = *index.entry(label).or_insert_with(|| {
+            groups.push(Vec::new());
+            groups.len() - 1
+        });
+
+        groups[i].push(val);
+    }
+
+    let (mut first, mut second) = (Vec::new(), Vec::new());
+    for group in groups {
+        let count = fraction_count(group.len(), fraction);
+        let (f, s) = split_vec(group, count, rng);
+        first.extend(f);
+        second.extend(s);
+    }
+
+    // NOTE: shuffle again, so the parts are not ordered by label
+    let (n, m) = (first.len(), second.len());
+    let (first, _) = split_vec(first, n, rng);
+    let (second, _) = split_vec(second, m, rng);
+    (first, second)
+}
+
+/// KFold is an iterator over the folds of k-fold cross validation, returned by `Dataset::kfold`
+/// and `Dataset::stratified_kfold`.
+///
+/// Every fold is a pair of train and validation datasets, and every element is in the
+/// validation dataset of exactly one fold.
+#[derive(Debug)]
+pub struct KFold<T> {
+    data: Vec<T>,
+    folds: Vec<usize>,
+    k: usize,
+    current: usize,
+}
+
+impl<T> KFold<T> {
+    pub(crate) fn new(data: Vec<T>, k: usize, seed: u64) -> KFold<T> {
+        assert!(k > 1, "kfold: k must be greater than 1");
+        assert!(
+            k <= data.len(),
+            "kfold: k must not be greater than the number of elements"
+        );
+
+        let rng = &mut XorShiftRng::seed_from_u64(seed);
+        let mut folds = vec![0; data.len()];
+        for (i, index) in permutation(data.len(), rng).into_iter().enumerate() {
+            folds[index] = i % k;
+        }
+
+        KFold {
+            data,
+            folds,
+            k,
+            current: 0,
+        }
+    }
+
+    pub(crate) fn new_stratified<K, F>(
+        data: Vec<T>,
+        k: usize,
+        seed: u64,
+        mut label_fn: F,
+    ) -> KFold<T>
+    where
+        K: Eq + Hash,
+        F: FnMut(&T) -> K,
+    {
+        assert!(k > 1, "kfold: k must be greater than 1");
+        assert!(
+            k <= data.len(),
+            "kfold: k must not be greater than the number of elements"
+        );
+
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut index: HashMap<K, usize> = HashMap::new();
+        for (i, val) in data.iter().enumerate() {
+            let g = *index.entry(label_fn(val)).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+
+            groups[g].push(i);
+        }
+
+        // NOTE: folds are assigned round robin across all labels, so fold sizes differ by at most 1
+        let rng = &mut XorShiftRng::seed_from_u64(seed);
+        let mut folds = vec![0; data.len()];
+        let mut next_fold = 0;
+        for group in groups {
+            for i in permutation(group.len(), rng) {
+                folds[group[i]] = next_fold;
+                next_fold = (next_fold + 1) % k;
+            }
+        }
+
+        KFold {
+            data,
+            folds,
+            k,
+            current: 0,
+        }
+    }
+}
+
+impl<T> Iterator for KFold<T>
+where
+    T: Clone,
+{
+    type Item = (vec::IntoIter<T>, vec::IntoIter<T>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current == self.k {
+            return None;
+        }
+
+        let (mut train, mut validation) = (Vec::new(), Vec::new());
+        for (val, fold) in self.data.iter().zip(&self.folds) {
+            if *fold == self.current {
+                validation.push(val.clone());
+            } else {
+                train.push(val.clone());
+            }
+        }
+
+        self.current += 1;
+        Some((train.into_iter(), validation.into_iter()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.k - self.current;
+        (n, Some(n))
+    }
+}
+
+impl<T> ExactSizeIterator for KFold<T> where T: Clone {}
@@ -0,0 +1,112 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::vec;
+
+use crate::dataset::{Cacheable, Checkpoint, Reiterable};
+
+/// Unbatch is an iterator that returns the elements of the batches of its inner iterator one
+/// at a time, the inverse of `batch`.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<usize> = vec![vec![0, 1, 2], vec![], vec![3, 4]]
+///     .into_iter()
+///     .unbatch()
+///     .collect();
+///
+/// assert_eq!(v, vec![0, 1, 2, 3, 4]);
+/// ```
+///
+/// Together with `batch`, this changes the size of the batches of a dataset, which is what
+/// `rebatch` does. Only one batch is held in memory at a time.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let v: Vec<Vec<usize>> = (0..7).batch(4, false).rebatch(2, false).collect();
+/// assert_eq!(v, vec![vec![0, 1], vec![2, 3], vec![4, 5], vec![6]]);
+/// ```
+pub struct Unbatch<I, T>
+where
+    I: Iterator<Item = Vec<T>>,
+{
+    iter: I,
+    current: vec::IntoIter<T>,
+}
+
+impl<I, T> Unbatch<I, T>
+where
+    I: Iterator<Item = Vec<T>>,
+{
+    pub(crate) fn new(iter: I) -> Unbatch<I, T> {
+        Unbatch {
+            iter,
+            current: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl<I, T> fmt::Debug for Unbatch<I, T>
+where
+    I: Iterator<Item = Vec<T>>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unbatch {{ remaining: {} }}", self.current.len())
+    }
+}
+
+impl<I, T> Reiterable for Unbatch<I, T>
+where
+    I: Reiterable + Iterator<Item = Vec<T>>,
+{
+    fn reiterate(&self, epoch: usize) -> Unbatch<I, T> {
+        Unbatch::new(self.iter.reiterate(epoch))
+    }
+}
+
+impl<I, T> Checkpoint for Unbatch<I, T>
+where
+    I: Iterator<Item = Vec<T>> + Checkpoint,
+    T: Cacheable,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.current.len().write_to(writer)?;
+        for val in self.current.as_slice() {
+            val.write_to(writer)?;
+        }
+
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.current = Vec::read_from(reader)?.into_iter();
+        self.iter.restore_state(reader)
+    }
+}
+
+impl<I, T> Iterator for Unbatch<I, T>
+where
+    I: Iterator<Item = Vec<T>>,
+{
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(val) = self.current.next() {
+                return Some(val);
+            }
+
+            self.current = self.iter.next()?.into_iter();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // the sizes of the remaining batches are unknown, unless there are none
+        let remaining = self.current.len();
+        match self.iter.size_hint() {
+            (_, Some(0)) => (remaining, Some(remaining)),
+            _ => (remaining, None),
+        }
+    }
+}
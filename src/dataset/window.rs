@@ -0,0 +1,191 @@
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use crate::dataset::cardinality;
+use crate::dataset::{Cacheable, Checkpoint, Reiterable};
+
+/// Window is an iterator that returns (possibly overlapping) windows over the elements of its
+/// inner iterator, with the semantics of `tf.data.Dataset.window`.
+///
+/// Each window contains `size` elements, taken `stride` elements apart, and consecutive
+/// windows start `shift` elements apart. A tumbling window is one where `shift == size`.
+///
+/// ```
+/// use datasets::Dataset;
+///
+/// let vals: Vec<Vec<usize>> = (0..7).window(3, 2, 1, false).collect();
+/// assert_eq!(vals, vec![vec![0, 1, 2], vec![2, 3, 4], vec![4, 5, 6], vec![6]]);
+///
+/// let vals: Vec<Vec<usize>> = (0..7).window(3, 2, 1, true).collect();
+/// assert_eq!(vals, vec![vec![0, 1, 2], vec![2, 3, 4], vec![4, 5, 6]]);
+///
+/// let vals: Vec<Vec<usize>> = (0..7).window(2, 1, 3, true).collect();
+/// assert_eq!(vals, vec![vec![0, 3], vec![1, 4], vec![2, 5], vec![3, 6]]);
+/// ```
+pub struct Window<I>
+where
+    I: Iterator,
+{
+    iter: I,
+    size: usize,
+    shift: usize,
+    stride: usize,
+    drop_remainder: bool,
+    buffer: VecDeque<<I as Iterator>::Item>,
+    skip: usize,
+}
+
+impl<I> Window<I>
+where
+    I: Iterator,
+{
+    pub(crate) fn new(
+        iter: I,
+        size: usize,
+        shift: usize,
+        stride: usize,
+        drop_remainder: bool,
+    ) -> Window<I> {
+        assert!(size > 0, "window: size must be greater than 0");
+        assert!(shift > 0, "window: shift must be greater than 0");
+        assert!(stride > 0, "window: stride must be greater than 0");
+
+        Window {
+            iter,
+            size,
+            shift,
+            stride,
+            drop_remainder,
+            buffer: VecDeque::with_capacity((size - 1) * stride + 1),
+            skip: 0,
+        }
+    }
+}
+
+impl<I> fmt::Debug for Window<I>
+where
+    I: Iterator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Window {{ size: {}, shift: {}, stride: {}, drop_remainder: {} }}",
+            self.size, self.shift, self.stride, self.drop_remainder
+        )
+    }
+}
+
+impl<I> Reiterable for Window<I>
+where
+    I: Reiterable,
+    <I as Iterator>::Item: Clone,
+{
+    fn reiterate(&self, epoch: usize) -> Window<I> {
+        Window::new(
+            self.iter.reiterate(epoch),
+            self.size,
+            self.shift,
+            self.stride,
+            self.drop_remainder,
+        )
+    }
+}
+
+impl<I> Checkpoint for Window<I>
+where
+    I: Iterator + Checkpoint,
+    <I as Iterator>::Item: Cacheable,
+{
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.skip.write_to(writer)?;
+        self.buffer.len().write_to(writer)?;
+        for val in &self.buffer {
+            val.write_to(writer)?;
+        }
+
+        self.iter.save_state(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        self.skip = usize::read_from(reader)?;
+        self.buffer = Vec::read_from(reader)?.into();
+        self.iter.restore_state(reader)
+    }
+}
+
+impl<I> Iterator for Window<I>
+where
+    I: Iterator,
+    <I as Iterator>::Item: Clone,
+{
+    type Item = Vec<<I as Iterator>::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // skip elements that the previous shift went past the end of the buffer
+        while self.skip > 0 {
+            self.iter.next()?;
+            self.skip -= 1;
+        }
+
+        // the number of elements from the start of the window to its last element
+        let span = (self.size - 1) * self.stride + 1;
+        while self.buffer.len() < span {
+            match self.iter.next() {
+                Some(val) => self.buffer.push_back(val),
+                None => break,
+            }
+        }
+
+        if self.buffer.is_empty() {
+            return None;
+        }
+
+        let window: Vec<_> = self
+            .buffer
+            .iter()
+            .step_by(self.stride)
+            .take(self.size)
+            .cloned()
+            .collect();
+
+        if self.shift < self.buffer.len() {
+            self.buffer.drain(..self.shift);
+        } else {
+            self.skip = self.shift - self.buffer.len();
+            self.buffer.clear();
+        }
+
+        if window.len() < self.size && self.drop_remainder {
+            None
+        } else {
+            Some(window)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        // the number of elements from the start of the next window to the end
+        let remaining = cardinality::add_hints(
+            cardinality::map_hint(self.iter.size_hint(), |n| n.saturating_sub(self.skip)),
+            (self.buffer.len(), Some(self.buffer.len())),
+        );
+
+        let span = (self.size - 1) * self.stride + 1;
+        cardinality::map_hint(remaining, |n| {
+            if !self.drop_remainder {
+                n.div_ceil(self.shift)
+            } else if n >= span {
+                (n - span) / self.shift + 1
+            } else {
+                0
+            }
+        })
+    }
+}
+
+impl<I> ExactSizeIterator for Window<I>
+where
+    I: ExactSizeIterator,
+    <I as Iterator>::Item: Clone,
+{
+}
@@ -2,15 +2,16 @@
 
 use std::error::Error;
 
-use crate::Dataset;
+use crate::{Checkpoint, IndexedDataset, Reiterable};
 
 pub mod mnist;
 
 /// a shorthand for mnist::load, will simply download and load from `$HOME/.datasets/mnist`
+#[allow(clippy::type_complexity)]
 pub fn mnist() -> Result<
     (
-        impl Dataset<Item = (Vec<u8>, u8)>,
-        impl Dataset<Item = (Vec<u8>, u8)>,
+        impl Reiterable<Item = (Vec<u8>, u8)> + Checkpoint + IndexedDataset,
+        impl Reiterable<Item = (Vec<u8>, u8)> + Checkpoint + IndexedDataset,
     ),
     Box<dyn Error>,
 > {
@@ -2,11 +2,57 @@
 
 use std::error::Error;
 use std::fs::File;
-use std::io::{Read, Seek, SeekFrom};
-use std::path::Path;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
 
-use crate::utils::download;
-use crate::Dataset;
+use crate::dataset::invalid_state;
+use crate::utils::{self, Checksum, Downloader};
+use crate::{Cacheable, Checkpoint, IndexedDataset, Reiterable, Split};
+
+// sizes of the idx file headers, and of a single image
+const FEATURES_OFFSET: u64 = 16;
+const LABELS_OFFSET: u64 = 8;
+const IMAGE_SIZE: usize = 28 * 28;
+
+// yann.lecun.com is often unavailable, so the copies used by torchvision and tensorflow come
+// first
+const MIRRORS: [&str; 3] = [
+    "https://ossci-datasets.s3.amazonaws.com/mnist/",
+    "https://storage.googleapis.com/cvdf-datasets/mnist/",
+    "http://yann.lecun.com/exdb/mnist/",
+];
+
+// the downloaded files, with their published sizes and sha256 digests
+const FILES: [(&str, Checksum<'static>); 4] = [
+    (
+        "train-images-idx3-ubyte.gz",
+        Checksum {
+            sha256: "440fcabf73cc546fa21475e81ea370265605f56be210a4024d2ca8f203523609",
+            size: Some(9_912_422),
+        },
+    ),
+    (
+        "train-labels-idx1-ubyte.gz",
+        Checksum {
+            sha256: "3552534a0a558bbed6aed32b30c495cca23d567ec52cac8be1a0730e8010255c",
+            size: Some(28_881),
+        },
+    ),
+    (
+        "t10k-images-idx3-ubyte.gz",
+        Checksum {
+            sha256: "8d422c7b0a1c1c79245a5bcf07fe86e33eeafee792b84584aec276f5a2dbc4e6",
+            size: Some(1_648_877),
+        },
+    ),
+    (
+        "t10k-labels-idx1-ubyte.gz",
+        Checksum {
+            sha256: "f7ae60f92e00ec6debd23a6088c31dbd2371eca3ffa0defaefb259924204aec6",
+            size: Some(4_542),
+        },
+    ),
+];
 
 /// http://yann.lecun.com/exdb/mnist/
 ///
@@ -22,46 +68,113 @@ use crate::Dataset;
 /// let img2D = (0..28)
 ///     .map(|_| iter.by_ref().take(28).collect())
 ///     .collect();
+///
+/// The datasets are `Reiterable`, so they can be repeated for multiple epochs, and
+/// `Checkpoint`, so iteration can be resumed from a saved position.
+///
+/// The datasets are also `IndexedDataset`s, which read images from disk on demand, so
+/// `permuted(seed)` shuffles them without loading every image into memory, unlike
+/// `shuffle_all`.
+///
+/// The files are reopened for every epoch, and reiterating panics if that fails, for example
+/// because they were deleted. An error reading a file ends the epoch early.
+///
+/// The files are downloaded from the first of several mirrors that serves them, and are checked
+/// against their published checksums before they are extracted.
+///
+/// Skipping elements, for example with `nth` or `shard`, seeks directly to the next record
+/// instead of reading the skipped ones.
+#[allow(clippy::type_complexity)]
 pub fn load(
     download_dir: &Path,
 ) -> Result<
     (
-        impl Dataset<Item = (Vec<u8>, u8)>,
-        impl Dataset<Item = (Vec<u8>, u8)>,
+        impl Reiterable<Item = (Vec<u8>, u8)> + Checkpoint + IndexedDataset,
+        impl Reiterable<Item = (Vec<u8>, u8)> + Checkpoint + IndexedDataset,
+    ),
+    Box<dyn Error>,
+> {
+    load_with(download_dir, &Downloader::new())
+}
+
+/// load_with is `load`, downloading with the given `Downloader`.
+#[allow(clippy::type_complexity)]
+pub fn load_with(
+    download_dir: &Path,
+    downloader: &Downloader,
+) -> Result<
+    (
+        impl Reiterable<Item = (Vec<u8>, u8)> + Checkpoint + IndexedDataset,
+        impl Reiterable<Item = (Vec<u8>, u8)> + Checkpoint + IndexedDataset,
     ),
     Box<dyn Error>,
 > {
-    download(
-        "http://yann.lecun.com/exdb/mnist/train-images-idx3-ubyte.gz",
-        download_dir,
-        true,
-    )?;
-    download(
-        "http://yann.lecun.com/exdb/mnist/train-labels-idx1-ubyte.gz",
-        download_dir,
-        true,
-    )?;
-    download(
-        "http://yann.lecun.com/exdb/mnist/t10k-images-idx3-ubyte.gz",
-        download_dir,
-        true,
-    )?;
-    download(
-        "http://yann.lecun.com/exdb/mnist/t10k-labels-idx1-ubyte.gz",
-        download_dir,
-        true,
-    )?;
+    load_datasets(download_dir, downloader)
+}
+
+/// Downloads the mnist dataset to the given `download_dir` and loads the given split from it.
+///
+/// The split is written as in tensorflow datasets, with "train" and "test" as the named splits,
+/// for example "train[:90%]" for the first 54000 training images.
+///
+/// ```no_run
+/// # use std::error::Error;
+/// #
+/// use datasets::image::mnist;
+///
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # let download_dir = std::path::Path::new(".");
+/// let train_data = mnist::load_split(download_dir, "train[:90%]")?;
+/// let validation_data = mnist::load_split(download_dir, "train[90%:]")?;
+///
+/// #   Ok(())
+/// # }
+/// ```
+pub fn load_split(
+    download_dir: &Path,
+    split: &str,
+) -> Result<impl Reiterable<Item = (Vec<u8>, u8)> + Checkpoint, Box<dyn Error>> {
+    load_split_with(download_dir, split, &Downloader::new())
+}
+
+/// load_split_with is `load_split`, downloading with the given `Downloader`.
+pub fn load_split_with(
+    download_dir: &Path,
+    split: &str,
+    downloader: &Downloader,
+) -> Result<impl Reiterable<Item = (Vec<u8>, u8)> + Checkpoint, Box<dyn Error>> {
+    let split: Split = split.parse()?;
+    let (train, test) = load_datasets(download_dir, downloader)?;
+
+    split.load(|name| match name {
+        "train" => Ok((train.reiterate(0), train.size)),
+        "test" => Ok((test.reiterate(0), test.size)),
+        _ => Err(format!("unknown mnist split: {:?}", name).into()),
+    })
+}
+
+/// Checks the mnist files already downloaded to `download_dir` against their published
+/// checksums, deleting the ones that do not match so that the next `load` downloads them again.
+pub fn verify(download_dir: &Path) -> Result<(), Box<dyn Error>> {
+    utils::verify(download_dir, &FILES)
+}
+
+fn load_datasets(
+    download_dir: &Path,
+    downloader: &Downloader,
+) -> Result<(MNISTDataset, MNISTDataset), Box<dyn Error>> {
+    for (file_name, checksum) in FILES.iter() {
+        downloader.download_from(&MIRRORS, file_name, download_dir, true, Some(*checksum))?;
+    }
 
     Ok((
         extract_dataset(
             &download_dir.join("train-images.idx3-ubyte"),
             &download_dir.join("train-labels.idx1-ubyte"),
-            60000,
         )?,
         extract_dataset(
             &download_dir.join("t10k-images.idx3-ubyte"),
             &download_dir.join("t10k-labels.idx1-ubyte"),
-            60000,
         )?,
     ))
 }
@@ -69,20 +182,31 @@ pub fn load(
 fn extract_dataset(
     features_path: &Path,
     labels_path: &Path,
-    size: usize,
-) -> Result<impl Dataset<Item = (Vec<u8>, u8)>, Box<dyn Error>> {
+) -> Result<MNISTDataset, Box<dyn Error>> {
     let (mut features, mut labels) = (File::open(features_path)?, File::open(labels_path)?);
 
-    features.seek(SeekFrom::Start(16))?;
-    labels.seek(SeekFrom::Start(8))?;
+    // the number of items is stored after the magic number in the labels header
+    let mut size = [0; 4];
+    labels.seek(SeekFrom::Start(4))?;
+    labels.read_exact(&mut size)?;
+    let size = u32::from_be_bytes(size) as usize;
+
+    features.seek(SeekFrom::Start(FEATURES_OFFSET))?;
+    labels.seek(SeekFrom::Start(LABELS_OFFSET))?;
 
-    Ok(MNISTDataset::new(features, labels, size))
+    Ok(MNISTDataset::new(
+        (features, labels),
+        features_path.to_path_buf(),
+        labels_path.to_path_buf(),
+        size,
+    ))
 }
 
 #[derive(Debug)]
 struct MNISTDataset {
-    features: File,
-    labels: File,
+    files: (File, File),
+    features_path: PathBuf,
+    labels_path: PathBuf,
     size: usize,
     current: usize,
     image_buffer: Vec<u8>,
@@ -90,18 +214,58 @@ struct MNISTDataset {
 }
 
 impl MNISTDataset {
-    fn new(features: File, labels: File, size: usize) -> MNISTDataset {
-        let (image_buffer, label_buffer) = (vec![0; 28 * 28], vec![0; 1]);
+    fn new(
+        files: (File, File),
+        features_path: PathBuf,
+        labels_path: PathBuf,
+        size: usize,
+    ) -> MNISTDataset {
+        let (image_buffer, label_buffer) = (vec![0; IMAGE_SIZE], vec![0; 1]);
 
         MNISTDataset {
-            features,
-            labels,
+            files,
+            features_path,
+            labels_path,
             size,
             current: 0,
             image_buffer,
             label_buffer,
         }
     }
+
+    /// moves both files to the record at `index`.
+    fn seek(&mut self, index: usize) -> io::Result<()> {
+        let (features, labels) = &mut self.files;
+        features.seek(SeekFrom::Start(
+            FEATURES_OFFSET + (index * IMAGE_SIZE) as u64,
+        ))?;
+        labels.seek(SeekFrom::Start(LABELS_OFFSET + index as u64))?;
+        self.current = index;
+        Ok(())
+    }
+}
+
+impl Checkpoint for MNISTDataset {
+    fn save_state(&self, writer: &mut dyn Write) -> io::Result<()> {
+        self.current.write_to(writer)
+    }
+
+    fn restore_state(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let current = usize::read_from(reader)?;
+        if current > self.size {
+            return Err(invalid_state("position past the end of the dataset"));
+        }
+
+        self.seek(current)
+    }
+}
+
+impl Reiterable for MNISTDataset {
+    fn reiterate(&self, _epoch: usize) -> MNISTDataset {
+        // NOTE: the files are reopened, as clones of a File share the same cursor
+        extract_dataset(&self.features_path, &self.labels_path)
+            .unwrap_or_else(|err| panic!("mnist: unable to reopen the files: {}", err))
+    }
 }
 
 impl Iterator for MNISTDataset {
@@ -111,12 +275,15 @@ impl Iterator for MNISTDataset {
         if self.current == self.size {
             None
         } else {
+            let (features, labels) = &mut self.files;
             let (r1, r2) = (
-                self.features.read_exact(&mut self.image_buffer),
-                self.labels.read_exact(&mut self.label_buffer),
+                features.read_exact(&mut self.image_buffer),
+                labels.read_exact(&mut self.label_buffer),
             );
 
             if r1.is_err() || r2.is_err() {
+                // NOTE: end the epoch, so that the size hint does not count records left unread
+                self.current = self.size;
                 return None;
             }
 
@@ -125,4 +292,40 @@ impl Iterator for MNISTDataset {
             Some((self.image_buffer.clone(), self.label_buffer[0]))
         }
     }
+
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        if n >= self.size - self.current {
+            self.current = self.size;
+            return None;
+        }
+
+        if n > 0 && self.seek(self.current + n).is_err() {
+            self.current = self.size;
+            return None;
+        }
+
+        self.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let n = self.size - self.current;
+        (n, Some(n))
+    }
+}
+
+impl ExactSizeIterator for MNISTDataset {}
+
+impl IndexedDataset for MNISTDataset {
+    fn get(&mut self, index: usize) -> Option<Self::Item> {
+        if index >= self.len() {
+            return None;
+        }
+
+        let current = self.current;
+        let val = self.seek(current + index).ok().and_then(|_| self.next());
+
+        // NOTE: move back to the position of iteration
+        self.seek(current).ok()?;
+        val
+    }
 }
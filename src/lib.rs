@@ -4,9 +4,14 @@
 
 mod dataset;
 
-pub use crate::dataset::Dataset;
+pub use crate::dataset::{
+    choose_from_datasets, sample_from_datasets, Cacheable, Cardinality, Checkpoint, Dataset,
+    IndexedDataset, Reiterable, Split,
+};
 
 pub mod image;
+#[cfg(feature = "stream")]
+pub mod stream;
 pub mod text;
 
 pub mod utils;
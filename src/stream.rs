@@ -0,0 +1,492 @@
+//! Asynchronous datasets, available with the `stream` feature.
+//!
+//! `Dataset::into_stream` turns a dataset into a `futures::Stream`, and `DatasetStream` adds
+//! `shuffle`, `batch` and `prefetch` to every stream.
+//!
+//! ```
+//! use datasets::stream::DatasetStream;
+//! use datasets::Dataset;
+//! use futures::executor::block_on;
+//! use futures::StreamExt;
+//!
+//! let v: Vec<usize> = block_on((0..8).into_stream(2).shuffle(4, 0).collect());
+//! assert_eq!(v, (0..8).shuffle(4, 0).collect::<Vec<_>>());
+//!
+//! let batches: Vec<Vec<usize>> = block_on((0..8).into_stream(2).batch(3, false).collect());
+//! assert_eq!(batches, vec![vec![0, 1, 2], vec![3, 4, 5], vec![6, 7]]);
+//! ```
+//!
+//! The loaders and `utils::download` are blocking, `load` and `utils::download_async` run them
+//! on a background thread and return a future for the result.
+//!
+//! Nothing here depends on a particular runtime. `Prefetch` polls its inner stream from the
+//! consumer's task, and the background threads of `into_stream` and `spawn_blocking` only wait
+//! on channels, so they work with any executor.
+
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+use std::future::Future;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::thread::{self, JoinHandle};
+
+use futures::channel::{mpsc, oneshot};
+use futures::executor::block_on;
+use futures::{ready, FutureExt, SinkExt, Stream, StreamExt};
+use rand::{Rng, SeedableRng};
+use rand_xorshift::XorShiftRng;
+
+/// DatasetStream adds the adapters of `Dataset` that make sense for streams to every
+/// `futures::Stream`.
+pub trait DatasetStream: Stream {
+    /// shuffle
+    ///
+    /// This returns elements in the same order as `Dataset::shuffle` with the same seed.
+    ///
+    /// This will panic if buffer_size is 0.
+    fn shuffle(self, buffer_size: usize, seed: u64) -> Shuffle<Self>
+    where
+        Self: Sized,
+    {
+        assert!(
+            buffer_size > 0,
+            "shuffle: buffer_size must be greater than 0"
+        );
+
+        Shuffle {
+            stream: Box::pin(self),
+            buffer_size,
+            buffer: Vec::with_capacity(buffer_size),
+            filled: false,
+            exhausted: false,
+            rng: XorShiftRng::seed_from_u64(seed),
+        }
+    }
+
+    /// batch
+    fn batch(self, batch_size: usize, drop_remainder: bool) -> Batch<Self>
+    where
+        Self: Sized,
+    {
+        Batch {
+            stream: Box::pin(self),
+            batch_size,
+            drop_remainder,
+            current: Vec::with_capacity(batch_size),
+            exhausted: false,
+        }
+    }
+
+    /// prefetch
+    fn prefetch(self, buffer_size: usize) -> Prefetch<Self>
+    where
+        Self: Sized,
+    {
+        Prefetch::new(self, buffer_size)
+    }
+}
+
+impl<S> DatasetStream for S where S: Stream {}
+
+/// Shuffle is a stream that takes buffer_size elements of its inner stream when it is first
+/// polled and returns them shuffled, replacing each returned element with the next one.
+pub struct Shuffle<S>
+where
+    S: Stream,
+{
+    stream: Pin<Box<S>>,
+    buffer_size: usize,
+    buffer: Vec<<S as Stream>::Item>,
+    filled: bool,
+    exhausted: bool,
+    rng: XorShiftRng,
+}
+
+impl<S> fmt::Debug for Shuffle<S>
+where
+    S: Stream,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Shuffle {{ buffer_size: {}, rng: {:?} }}",
+            self.buffer_size, self.rng
+        )
+    }
+}
+
+// NOTE: the buffered elements are never pinned, only the inner stream is
+impl<S> Unpin for Shuffle<S> where S: Stream {}
+
+impl<S> Stream for Shuffle<S>
+where
+    S: Stream,
+{
+    type Item = <S as Stream>::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        while !this.filled {
+            if this.buffer.len() == this.buffer_size {
+                this.filled = true;
+            } else {
+                match ready!(this.stream.as_mut().poll_next(cx)) {
+                    Some(val) => this.buffer.push(val),
+                    None => {
+                        this.exhausted = true;
+                        this.filled = true;
+                    }
+                }
+            }
+        }
+
+        if this.buffer.is_empty() {
+            return Poll::Ready(None);
+        }
+
+        if !this.exhausted {
+            match ready!(this.stream.as_mut().poll_next(cx)) {
+                Some(val) => {
+                    let index = this.rng.gen_range(0, this.buffer.len());
+                    return Poll::Ready(Some(mem::replace(&mut this.buffer[index], val)));
+                }
+                None => this.exhausted = true,
+            }
+        }
+
+        let index = this.rng.gen_range(0, this.buffer.len());
+        Poll::Ready(Some(this.buffer.swap_remove(index)))
+    }
+}
+
+/// Batch is a stream that returns the contents of its inner stream in batches.
+pub struct Batch<S>
+where
+    S: Stream,
+{
+    stream: Pin<Box<S>>,
+    batch_size: usize,
+    drop_remainder: bool,
+    current: Vec<<S as Stream>::Item>,
+    exhausted: bool,
+}
+
+impl<S> fmt::Debug for Batch<S>
+where
+    S: Stream,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Batch {{ batch_size: {}, drop_remainder: {} }}",
+            self.batch_size, self.drop_remainder
+        )
+    }
+}
+
+impl<S> Unpin for Batch<S> where S: Stream {}
+
+impl<S> Stream for Batch<S>
+where
+    S: Stream,
+{
+    type Item = Vec<<S as Stream>::Item>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        // NOTE: like `Dataset::batch`, a batch_size of 0 returns batches of 1
+        let batch_size = this.batch_size.max(1);
+
+        while !this.exhausted {
+            match ready!(this.stream.as_mut().poll_next(cx)) {
+                Some(val) => {
+                    this.current.push(val);
+                    if this.current.len() == batch_size {
+                        let batch = mem::replace(&mut this.current, Vec::with_capacity(batch_size));
+                        return Poll::Ready(Some(batch));
+                    }
+                }
+                None => this.exhausted = true,
+            }
+        }
+
+        if this.current.is_empty() || (this.drop_remainder && this.current.len() < batch_size) {
+            Poll::Ready(None)
+        } else {
+            Poll::Ready(Some(mem::take(&mut this.current)))
+        }
+    }
+}
+
+/// Prefetch is a stream that polls its inner stream ahead of the consumer, keeping up to
+/// `buffer_size` elements ready.
+///
+/// The inner stream is polled from the task that polls the Prefetch, so it runs on the same
+/// executor, and streams that need the reactor or timers of a runtime work as they would
+/// without it. To produce the elements of a blocking dataset on another thread, use
+/// `Dataset::into_stream` instead.
+///
+/// ```
+/// use datasets::stream::DatasetStream;
+/// use futures::executor::block_on;
+/// use futures::{stream, StreamExt};
+///
+/// let v: Vec<usize> = block_on(stream::iter(0..4).prefetch(2).collect());
+/// assert_eq!(v, vec![0, 1, 2, 3]);
+/// ```
+pub struct Prefetch<S>
+where
+    S: Stream,
+{
+    stream: Pin<Box<S>>,
+    buffer_size: usize,
+    buffer: VecDeque<<S as Stream>::Item>,
+    exhausted: bool,
+}
+
+impl<S> Prefetch<S>
+where
+    S: Stream,
+{
+    pub(crate) fn new(stream: S, buffer_size: usize) -> Prefetch<S> {
+        Prefetch {
+            stream: Box::pin(stream),
+            buffer_size,
+            buffer: VecDeque::with_capacity(buffer_size),
+            exhausted: false,
+        }
+    }
+}
+
+impl<S> fmt::Debug for Prefetch<S>
+where
+    S: Stream,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Prefetch {{ buffer_size: {}, ready: {} }}",
+            self.buffer_size,
+            self.buffer.len()
+        )
+    }
+}
+
+impl<S> Unpin for Prefetch<S> where S: Stream {}
+
+impl<S> Stream for Prefetch<S>
+where
+    S: Stream,
+{
+    type Item = <S as Stream>::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        // NOTE: like `Dataset::prefetch`, a buffer_size of 0 still returns every element
+        while !this.exhausted && this.buffer.len() < this.buffer_size.max(1) {
+            match this.stream.as_mut().poll_next(cx) {
+                Poll::Ready(Some(val)) => this.buffer.push_back(val),
+                Poll::Ready(None) => this.exhausted = true,
+                Poll::Pending => break,
+            }
+        }
+
+        match this.buffer.pop_front() {
+            Some(val) => Poll::Ready(Some(val)),
+            None if this.exhausted => Poll::Ready(None),
+            None => Poll::Pending,
+        }
+    }
+}
+
+/// IntoStream is the stream returned by `Dataset::into_stream`, which runs its dataset on a
+/// background thread, keeping up to `buffer_size` elements ready ahead of the consumer.
+///
+/// The thread is started when the stream is first polled, and only waits on a channel, so the
+/// stream works with any executor. If the dataset panics, the panic is resumed when the stream
+/// is polled after its last element. When an IntoStream is dropped, it waits for the thread to
+/// finish the element it is currently producing.
+pub struct IntoStream<I>
+where
+    I: Iterator,
+{
+    iter: Option<I>,
+    buffer_size: usize,
+    receiver: Option<mpsc::Receiver<thread::Result<<I as Iterator>::Item>>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl<I> IntoStream<I>
+where
+    I: Iterator + Send + 'static,
+    <I as Iterator>::Item: Send + 'static,
+{
+    pub(crate) fn new(iter: I, buffer_size: usize) -> IntoStream<I> {
+        IntoStream {
+            iter: Some(iter),
+            buffer_size,
+            receiver: None,
+            worker: None,
+        }
+    }
+
+    fn start(&mut self) {
+        if let Some(iter) = self.iter.take() {
+            let (mut sender, receiver) = mpsc::channel(self.buffer_size);
+
+            let worker = thread::spawn(move || {
+                let produced = panic::catch_unwind(AssertUnwindSafe(|| {
+                    for val in iter {
+                        // NOTE: an error here means the receiver was dropped, so stop producing
+                        if block_on(sender.send(Ok(val))).is_err() {
+                            return;
+                        }
+                    }
+                }));
+
+                // the panic is sent after the elements, to be resumed by the consumer
+                if let Err(err) = produced {
+                    let _ = block_on(sender.send(Err(err)));
+                }
+            });
+
+            self.receiver = Some(receiver);
+            self.worker = Some(worker);
+        }
+    }
+}
+
+impl<I> fmt::Debug for IntoStream<I>
+where
+    I: Iterator,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "IntoStream {{ buffer_size: {}, started: {} }}",
+            self.buffer_size,
+            self.iter.is_none()
+        )
+    }
+}
+
+impl<I> Unpin for IntoStream<I> where I: Iterator {}
+
+impl<I> Stream for IntoStream<I>
+where
+    I: Iterator + Send + 'static,
+    <I as Iterator>::Item: Send + 'static,
+{
+    type Item = <I as Iterator>::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        this.start();
+
+        let val = match this.receiver.as_mut() {
+            Some(receiver) => ready!(receiver.poll_next_unpin(cx)),
+            None => None,
+        };
+
+        match val {
+            Some(Ok(val)) => Poll::Ready(Some(val)),
+            Some(Err(err)) => {
+                this.receiver = None;
+                panic::resume_unwind(err)
+            }
+            None => {
+                this.receiver = None;
+                Poll::Ready(None)
+            }
+        }
+    }
+}
+
+impl<I> Drop for IntoStream<I>
+where
+    I: Iterator,
+{
+    fn drop(&mut self) {
+        // dropping the receiver makes the next send on the worker fail
+        self.receiver = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Blocking is a future for the result of a function running on a background thread,
+/// returned by `spawn_blocking`.
+#[derive(Debug)]
+pub struct Blocking<T> {
+    receiver: oneshot::Receiver<thread::Result<T>>,
+}
+
+impl<T> Future for Blocking<T> {
+    type Output = T;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Self::Output> {
+        match ready!(self.receiver.poll_unpin(cx)) {
+            Ok(Ok(val)) => Poll::Ready(val),
+            Ok(Err(err)) => panic::resume_unwind(err),
+            // NOTE: the sender is only dropped after sending the result
+            Err(_) => unreachable!("the result of a blocking function was not sent"),
+        }
+    }
+}
+
+/// spawn_blocking runs `f` on a new thread, and returns a future for its result.
+///
+/// If `f` panics, the panic is resumed when the future is polled.
+///
+/// This starts a thread for every call, which is meant for long running work like loading a
+/// dataset. Within a runtime that has a pool for blocking work, like tokio, its own
+/// `spawn_blocking` reuses threads instead.
+///
+/// ```
+/// use datasets::stream;
+/// use futures::executor::block_on;
+///
+/// assert_eq!(block_on(stream::spawn_blocking(|| 1 + 1)), 2);
+/// ```
+pub fn spawn_blocking<F, T>(f: F) -> Blocking<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let (sender, receiver) = oneshot::channel();
+
+    thread::spawn(move || {
+        let _ = sender.send(panic::catch_unwind(AssertUnwindSafe(f)));
+    });
+
+    Blocking { receiver }
+}
+
+/// load runs a loader on a new thread with `spawn_blocking`, and returns a future for the loaded
+/// datasets.
+///
+/// Errors are not always `Send`, so they are returned with their message only.
+///
+/// ```no_run
+/// use datasets::image::mnist;
+/// use datasets::stream;
+/// use futures::executor::block_on;
+/// use std::path::PathBuf;
+///
+/// let dir = PathBuf::from("/tmp/mnist");
+/// let (train, test) = block_on(stream::load(move || mnist::load(&dir))).unwrap();
+/// ```
+pub fn load<F, T>(f: F) -> Blocking<Result<T, Box<dyn Error + Send + Sync>>>
+where
+    F: FnOnce() -> Result<T, Box<dyn Error>> + Send + 'static,
+    T: Send + 'static,
+{
+    spawn_blocking(move || f().map_err(|err| err.to_string().into()))
+}
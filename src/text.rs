@@ -2,7 +2,7 @@
 
 use std::error::Error;
 
-use crate::Dataset;
+use crate::{Checkpoint, IndexedDataset, Reiterable};
 
 pub mod babi;
 pub mod enron;
@@ -10,10 +10,11 @@ pub mod imdb;
 pub mod shakespeare;
 
 /// a shorthand for imdb_reviews::load, will simply download and load from `$HOME/.datasets/mnist`
+#[allow(clippy::type_complexity)]
 pub fn imdb_reviews() -> Result<
     (
-        impl Dataset<Item = (String, u8)>,
-        impl Dataset<Item = (String, u8)>,
+        impl Reiterable<Item = (String, u8)> + Checkpoint + IndexedDataset,
+        impl Reiterable<Item = (String, u8)> + Checkpoint + IndexedDataset,
     ),
     Box<dyn Error>,
 > {
@@ -26,22 +27,25 @@ pub fn imdb_reviews() -> Result<
 }
 
 /// shorthand for babi::load_en_single_supporting_fact_task
+#[allow(clippy::type_complexity)]
 pub fn babi_en_single_supporting_fact_task() -> Result<
     (
-        impl Dataset<
-            Item = (
-                std::string::String,
-                std::string::String,
-                (std::string::String, std::string::String, usize),
-            ),
-        >,
-        impl Dataset<
-            Item = (
-                std::string::String,
-                std::string::String,
-                (std::string::String, std::string::String, usize),
-            ),
-        >,
+        impl Reiterable<
+                Item = (
+                    std::string::String,
+                    std::string::String,
+                    (std::string::String, std::string::String, usize),
+                ),
+            > + Checkpoint
+            + IndexedDataset,
+        impl Reiterable<
+                Item = (
+                    std::string::String,
+                    std::string::String,
+                    (std::string::String, std::string::String, usize),
+                ),
+            > + Checkpoint
+            + IndexedDataset,
     ),
     Box<dyn Error>,
 > {
@@ -55,22 +59,25 @@ pub fn babi_en_single_supporting_fact_task() -> Result<
 }
 
 /// shorthand for babi::load_hn_single_supporting_fact_task
+#[allow(clippy::type_complexity)]
 pub fn babi_hn_single_supporting_fact_task() -> Result<
     (
-        impl Dataset<
-            Item = (
-                std::string::String,
-                std::string::String,
-                (std::string::String, std::string::String, usize),
-            ),
-        >,
-        impl Dataset<
-            Item = (
-                std::string::String,
-                std::string::String,
-                (std::string::String, std::string::String, usize),
-            ),
-        >,
+        impl Reiterable<
+                Item = (
+                    std::string::String,
+                    std::string::String,
+                    (std::string::String, std::string::String, usize),
+                ),
+            > + Checkpoint
+            + IndexedDataset,
+        impl Reiterable<
+                Item = (
+                    std::string::String,
+                    std::string::String,
+                    (std::string::String, std::string::String, usize),
+                ),
+            > + Checkpoint
+            + IndexedDataset,
     ),
     Box<dyn Error>,
 > {
@@ -95,8 +102,13 @@ pub fn shakespeare_100000() -> Result<String, Box<dyn Error>> {
 }
 
 /// enron::spam
-pub fn enron_spam(
-) -> Result<(impl Dataset<Item = String>, impl Dataset<Item = String>), Box<dyn Error>> {
+pub fn enron_spam() -> Result<
+    (
+        impl Reiterable<Item = String> + Checkpoint + IndexedDataset,
+        impl Reiterable<Item = String> + Checkpoint + IndexedDataset,
+    ),
+    Box<dyn Error>,
+> {
     enron::spam(
         &dirs::home_dir()
             .unwrap()
@@ -1,6 +1,9 @@
 //! https://research.fb.com/downloads/babi/
 //! https://github.com/facebook/bAbI-tasks
 //! https://arxiv.org/abs/1502.05698
+//!
+//! No checksum is published for the tasks archive, so unlike mnist and imdb reviews it is
+//! downloaded unverified, and there is no `verify` for it.
 
 use std::error::Error;
 use std::fs::File;
@@ -9,117 +12,297 @@ use std::path::Path;
 
 use regex::Regex;
 
-use crate::utils::download;
-use crate::Dataset;
+use crate::utils::Downloader;
+use crate::{Checkpoint, IndexedDataset, Reiterable, Split};
+
+const FILE_NAME: &str = "tasks_1-20_v1-2.tar.gz";
+
+const MIRRORS: [&str; 2] = [
+    "https://dl.fbaipublicfiles.com/babi/",
+    "http://www.thespermwhale.com/jaseweston/babi/",
+];
 
 /// load_en_single_supporting_fact_task loads the English Single Supporting Task dataset from
-/// http://www.thespermwhale.com/jaseweston/babi/tasks_1-20_v1-2.tar.gz
+/// https://dl.fbaipublicfiles.com/babi/tasks_1-20_v1-2.tar.gz
+#[allow(clippy::type_complexity)]
 pub fn load_en_single_supporting_fact_task(
     download_dir: &Path,
 ) -> Result<
     (
-        impl Dataset<
+        impl Reiterable<
+                Item = (
+                    std::string::String,
+                    std::string::String,
+                    (std::string::String, std::string::String, usize),
+                ),
+            > + Checkpoint
+            + IndexedDataset,
+        impl Reiterable<
+                Item = (
+                    std::string::String,
+                    std::string::String,
+                    (std::string::String, std::string::String, usize),
+                ),
+            > + Checkpoint
+            + IndexedDataset,
+    ),
+    Box<dyn Error>,
+> {
+    load_en_single_supporting_fact_task_with(download_dir, &Downloader::new())
+}
+
+/// load_en_single_supporting_fact_task_with is `load_en_single_supporting_fact_task`,
+/// downloading with the given `Downloader`.
+#[allow(clippy::type_complexity)]
+pub fn load_en_single_supporting_fact_task_with(
+    download_dir: &Path,
+    downloader: &Downloader,
+) -> Result<
+    (
+        impl Reiterable<
+                Item = (
+                    std::string::String,
+                    std::string::String,
+                    (std::string::String, std::string::String, usize),
+                ),
+            > + Checkpoint
+            + IndexedDataset,
+        impl Reiterable<
+                Item = (
+                    std::string::String,
+                    std::string::String,
+                    (std::string::String, std::string::String, usize),
+                ),
+            > + Checkpoint
+            + IndexedDataset,
+    ),
+    Box<dyn Error>,
+> {
+    downloader.download_from(&MIRRORS, FILE_NAME, download_dir, true, None)?;
+
+    Ok((
+        open_single_supporting_fact_task(download_dir, "en", "train")?,
+        open_single_supporting_fact_task(download_dir, "en", "test")?,
+    ))
+}
+
+/// load_en_single_supporting_fact_task_split loads the given split of the English Single
+/// Supporting Task dataset from https://dl.fbaipublicfiles.com/babi/tasks_1-20_v1-2.tar.gz
+///
+/// The split is written as in tensorflow datasets, with "train" and "test" as the named splits,
+/// for example "train[:90%]" for the first 90% of the training examples.
+#[allow(clippy::type_complexity)]
+pub fn load_en_single_supporting_fact_task_split(
+    download_dir: &Path,
+    split: &str,
+) -> Result<
+    impl Reiterable<
             Item = (
                 std::string::String,
                 std::string::String,
                 (std::string::String, std::string::String, usize),
             ),
-        >,
-        impl Dataset<
+        > + Checkpoint,
+    Box<dyn Error>,
+> {
+    load_en_single_supporting_fact_task_split_with(download_dir, split, &Downloader::new())
+}
+
+/// load_en_single_supporting_fact_task_split_with is
+/// `load_en_single_supporting_fact_task_split`, downloading with the given `Downloader`.
+#[allow(clippy::type_complexity)]
+pub fn load_en_single_supporting_fact_task_split_with(
+    download_dir: &Path,
+    split: &str,
+    downloader: &Downloader,
+) -> Result<
+    impl Reiterable<
             Item = (
                 std::string::String,
                 std::string::String,
                 (std::string::String, std::string::String, usize),
             ),
-        >,
-    ),
+        > + Checkpoint,
     Box<dyn Error>,
 > {
-    download(
-        "http://www.thespermwhale.com/jaseweston/babi/tasks_1-20_v1-2.tar.gz",
-        download_dir,
-        true,
-    )?;
+    load_single_supporting_fact_task_split(download_dir, "en", split, downloader)
+}
 
-    let train_data = File::open(
-        download_dir
-            .join("tasks_1-20_v1-2")
-            .join("en")
-            .join("qa1_single-supporting-fact_train.txt"),
-    )?;
+/// load_hn_single_supporting_fact_task loads the Hindi Single Supporting Task dataset from
+/// https://dl.fbaipublicfiles.com/babi/tasks_1-20_v1-2.tar.gz
+#[allow(clippy::type_complexity)]
+pub fn load_hn_single_supporting_fact_task(
+    download_dir: &Path,
+) -> Result<
+    (
+        impl Reiterable<
+                Item = (
+                    std::string::String,
+                    std::string::String,
+                    (std::string::String, std::string::String, usize),
+                ),
+            > + Checkpoint
+            + IndexedDataset,
+        impl Reiterable<
+                Item = (
+                    std::string::String,
+                    std::string::String,
+                    (std::string::String, std::string::String, usize),
+                ),
+            > + Checkpoint
+            + IndexedDataset,
+    ),
+    Box<dyn Error>,
+> {
+    load_hn_single_supporting_fact_task_with(download_dir, &Downloader::new())
+}
 
-    let test_data = File::open(
-        download_dir
-            .join("tasks_1-20_v1-2")
-            .join("en")
-            .join("qa1_single-supporting-fact_test.txt"),
-    )?;
+/// load_hn_single_supporting_fact_task_with is `load_hn_single_supporting_fact_task`,
+/// downloading with the given `Downloader`.
+#[allow(clippy::type_complexity)]
+pub fn load_hn_single_supporting_fact_task_with(
+    download_dir: &Path,
+    downloader: &Downloader,
+) -> Result<
+    (
+        impl Reiterable<
+                Item = (
+                    std::string::String,
+                    std::string::String,
+                    (std::string::String, std::string::String, usize),
+                ),
+            > + Checkpoint
+            + IndexedDataset,
+        impl Reiterable<
+                Item = (
+                    std::string::String,
+                    std::string::String,
+                    (std::string::String, std::string::String, usize),
+                ),
+            > + Checkpoint
+            + IndexedDataset,
+    ),
+    Box<dyn Error>,
+> {
+    downloader.download_from(&MIRRORS, FILE_NAME, download_dir, true, None)?;
 
     Ok((
-        load_single_supporting_fact_task(train_data)?,
-        load_single_supporting_fact_task(test_data)?,
+        open_single_supporting_fact_task(download_dir, "hn", "train")?,
+        open_single_supporting_fact_task(download_dir, "hn", "test")?,
     ))
 }
 
-/// load_hn_single_supporting_fact_task loads the Hindi Single Supporting Task dataset from
-/// http://www.thespermwhale.com/jaseweston/babi/tasks_1-20_v1-2.tar.gz
-pub fn load_hn_single_supporting_fact_task(
+/// load_hn_single_supporting_fact_task_split loads the given split of the Hindi Single
+/// Supporting Task dataset from https://dl.fbaipublicfiles.com/babi/tasks_1-20_v1-2.tar.gz
+///
+/// The split is written as in tensorflow datasets, with "train" and "test" as the named splits,
+/// for example "train[:90%]" for the first 90% of the training examples.
+#[allow(clippy::type_complexity)]
+pub fn load_hn_single_supporting_fact_task_split(
     download_dir: &Path,
+    split: &str,
 ) -> Result<
-    (
-        impl Dataset<
+    impl Reiterable<
             Item = (
                 std::string::String,
                 std::string::String,
                 (std::string::String, std::string::String, usize),
             ),
-        >,
-        impl Dataset<
+        > + Checkpoint,
+    Box<dyn Error>,
+> {
+    load_hn_single_supporting_fact_task_split_with(download_dir, split, &Downloader::new())
+}
+
+/// load_hn_single_supporting_fact_task_split_with is
+/// `load_hn_single_supporting_fact_task_split`, downloading with the given `Downloader`.
+#[allow(clippy::type_complexity)]
+pub fn load_hn_single_supporting_fact_task_split_with(
+    download_dir: &Path,
+    split: &str,
+    downloader: &Downloader,
+) -> Result<
+    impl Reiterable<
             Item = (
                 std::string::String,
                 std::string::String,
                 (std::string::String, std::string::String, usize),
             ),
-        >,
-    ),
+        > + Checkpoint,
     Box<dyn Error>,
 > {
-    download(
-        "http://www.thespermwhale.com/jaseweston/babi/tasks_1-20_v1-2.tar.gz",
-        download_dir,
-        true,
-    )?;
+    load_single_supporting_fact_task_split(download_dir, "hn", split, downloader)
+}
 
-    let train_data = File::open(
-        download_dir
-            .join("tasks_1-20_v1-2")
-            .join("hn")
-            .join("qa1_single-supporting-fact_train.txt"),
-    )?;
+#[allow(clippy::type_complexity)]
+fn load_single_supporting_fact_task_split(
+    download_dir: &Path,
+    language: &str,
+    split: &str,
+    downloader: &Downloader,
+) -> Result<
+    impl Reiterable<
+            Item = (
+                std::string::String,
+                std::string::String,
+                (std::string::String, std::string::String, usize),
+            ),
+        > + Checkpoint,
+    Box<dyn Error>,
+> {
+    let split: Split = split.parse()?;
+
+    downloader.download_from(&MIRRORS, FILE_NAME, download_dir, true, None)?;
+
+    split.load(|name| match name {
+        "train" | "test" => {
+            let dataset = open_single_supporting_fact_task(download_dir, language, name)?;
+            let len = dataset.len();
+            Ok((dataset, len))
+        }
+        _ => Err(format!("unknown babi split: {:?}", name).into()),
+    })
+}
 
-    let test_data = File::open(
+// opens the train or test file of the single supporting fact task in the given language
+#[allow(clippy::type_complexity)]
+fn open_single_supporting_fact_task(
+    download_dir: &Path,
+    language: &str,
+    name: &str,
+) -> Result<
+    impl Reiterable<
+            Item = (
+                std::string::String,
+                std::string::String,
+                (std::string::String, std::string::String, usize),
+            ),
+        > + Checkpoint
+        + IndexedDataset,
+    Box<dyn Error>,
+> {
+    let f = File::open(
         download_dir
             .join("tasks_1-20_v1-2")
-            .join("hn")
-            .join("qa1_single-supporting-fact_test.txt"),
+            .join(language)
+            .join(format!("qa1_single-supporting-fact_{}.txt", name)),
     )?;
 
-    Ok((
-        load_single_supporting_fact_task(train_data)?,
-        load_single_supporting_fact_task(test_data)?,
-    ))
+    load_single_supporting_fact_task(f)
 }
 
+#[allow(clippy::type_complexity)]
 fn load_single_supporting_fact_task(
     f: File,
 ) -> Result<
-    impl Dataset<
-        Item = (
-            std::string::String,
-            std::string::String,
-            (std::string::String, std::string::String, usize),
-        ),
-    >,
+    impl Reiterable<
+            Item = (
+                std::string::String,
+                std::string::String,
+                (std::string::String, std::string::String, usize),
+            ),
+        > + Checkpoint
+        + IndexedDataset,
     Box<dyn Error>,
 > {
     let line_regex = Regex::new(r"\d+\s+(.+)")?;
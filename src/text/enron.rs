@@ -5,8 +5,13 @@ use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
-use crate::utils::download;
-use crate::Dataset;
+use crate::utils::Downloader;
+use crate::{Checkpoint, IndexedDataset, Reiterable};
+
+const MIRRORS: [&str; 2] = [
+    "http://www.aueb.gr/users/ion/data/enron-spam/preprocessed/",
+    "http://www2.aueb.gr/users/ion/data/enron-spam/preprocessed/",
+];
 
 /// Enron Spam Dataset
 /// http://www2.aueb.gr/users/ion/data/enron-spam/
@@ -14,98 +19,65 @@ use crate::Dataset;
 /// This returns a pair of datasets with the preprocessed spam and ham messages.
 ///
 /// __NOTE:__ currently this will simply ignore and not return emails with invalid UTF-8.
+///
+/// The six archives are downloaded without checking them, since the dataset's page publishes no
+/// checksums for them.
+///
+/// The datasets are `Reiterable` and `Checkpoint`.
 pub fn spam(
     download_dir: &Path,
-) -> Result<(impl Dataset<Item = String>, impl Dataset<Item = String>), Box<dyn Error>> {
-    download(
-        "http://www.aueb.gr/users/ion/data/enron-spam/preprocessed/enron1.tar.gz",
-        download_dir,
-        true,
-    )?;
-
-    download(
-        "http://www.aueb.gr/users/ion/data/enron-spam/preprocessed/enron2.tar.gz",
-        download_dir,
-        true,
-    )?;
-
-    download(
-        "http://www.aueb.gr/users/ion/data/enron-spam/preprocessed/enron3.tar.gz",
-        download_dir,
-        true,
-    )?;
+) -> Result<
+    (
+        impl Reiterable<Item = String> + Checkpoint + IndexedDataset,
+        impl Reiterable<Item = String> + Checkpoint + IndexedDataset,
+    ),
+    Box<dyn Error>,
+> {
+    spam_with(download_dir, &Downloader::new())
+}
 
-    download(
-        "http://www.aueb.gr/users/ion/data/enron-spam/preprocessed/enron4.tar.gz",
-        download_dir,
-        true,
-    )?;
+/// spam_with is `spam`, downloading with the given `Downloader`.
+pub fn spam_with(
+    download_dir: &Path,
+    downloader: &Downloader,
+) -> Result<
+    (
+        impl Reiterable<Item = String> + Checkpoint + IndexedDataset,
+        impl Reiterable<Item = String> + Checkpoint + IndexedDataset,
+    ),
+    Box<dyn Error>,
+> {
+    for i in 1..=6 {
+        let file_name = format!("enron{}.tar.gz", i);
+        downloader.download_from(&MIRRORS, &file_name, download_dir, true, None)?;
+    }
 
-    download(
-        "http://www.aueb.gr/users/ion/data/enron-spam/preprocessed/enron5.tar.gz",
-        download_dir,
-        true,
-    )?;
+    let (mut spam, mut ham) = (Vec::new(), Vec::new());
 
-    download(
-        "http://www.aueb.gr/users/ion/data/enron-spam/preprocessed/enron6.tar.gz",
-        download_dir,
-        true,
-    )?;
+    for i in 1..=6 {
+        let dir = download_dir.join(format!("enron{}", i));
+        load_directory_dataset(&dir.join("spam"), &mut spam)?;
+        load_directory_dataset(&dir.join("ham"), &mut ham)?;
+    }
 
-    Ok((
-        load_directory_dataset(&download_dir.join("enron1").join("spam"))?
-            .chain(load_directory_dataset(
-                &download_dir.join("enron2").join("spam"),
-            )?)
-            .chain(load_directory_dataset(
-                &download_dir.join("enron3").join("spam"),
-            )?)
-            .chain(load_directory_dataset(
-                &download_dir.join("enron4").join("spam"),
-            )?)
-            .chain(load_directory_dataset(
-                &download_dir.join("enron5").join("spam"),
-            )?)
-            .chain(load_directory_dataset(
-                &download_dir.join("enron6").join("spam"),
-            )?),
-        load_directory_dataset(&download_dir.join("enron1").join("ham"))?
-            .chain(load_directory_dataset(
-                &download_dir.join("enron2").join("ham"),
-            )?)
-            .chain(load_directory_dataset(
-                &download_dir.join("enron3").join("ham"),
-            )?)
-            .chain(load_directory_dataset(
-                &download_dir.join("enron4").join("ham"),
-            )?)
-            .chain(load_directory_dataset(
-                &download_dir.join("enron5").join("ham"),
-            )?)
-            .chain(load_directory_dataset(
-                &download_dir.join("enron6").join("ham"),
-            )?),
-    ))
+    Ok((spam.into_iter(), ham.into_iter()))
 }
 
 // TODO: rayon?
-fn load_directory_dataset(dir: &Path) -> Result<impl Dataset<Item = String>, Box<dyn Error>> {
+fn load_directory_dataset(dir: &Path, v: &mut Vec<String>) -> Result<(), Box<dyn Error>> {
     let dir_reader = dir.read_dir()?;
 
-    let mut v = Vec::new();
-
     for entry in dir_reader {
         let entry = entry?;
 
         let mut email = String::new();
 
         // NOTE: ignoring invalid UTF-8 errors
-        match File::open(&entry.path())?.read_to_string(&mut email) {
+        match File::open(entry.path())?.read_to_string(&mut email) {
             Ok(_) => v.push(email),
             Err(_) => continue,
         }
     }
 
-    Ok(v.into_iter())
+    Ok(())
 }
@@ -5,9 +5,24 @@ use std::error::Error;
 use std::fs::File;
 use std::io::Read;
 use std::path::Path;
+use std::vec;
 
-use crate::utils::download;
-use crate::Dataset;
+use crate::utils::{self, Checksum, Downloader};
+use crate::{Checkpoint, Dataset, IndexedDataset, Reiterable, Split};
+
+const MIRRORS: [&str; 1] = ["https://ai.stanford.edu/~amaas/data/sentiment/"];
+
+// the seed the named splits are shuffled with before slicing, which must not change so that
+// slices stay the same between versions
+const SPLIT_SEED: u64 = 42;
+
+const FILE: (&str, Checksum<'static>) = (
+    "aclImdb_v1.tar.gz",
+    Checksum {
+        sha256: "c40f74a18d3b61f90feba1e17730e0d38e8b97c05fde7008942e91923d1658fe",
+        size: Some(84_125_825),
+    },
+);
 
 /// https://ai.stanford.edu/~amaas/data/sentiment/
 ///
@@ -33,20 +48,34 @@ use crate::Dataset;
 /// #   Ok(())
 /// # }
 /// ```
+///
+/// The datasets are `Reiterable` and `Checkpoint`.
+#[allow(clippy::type_complexity)]
 pub fn reviews(
     download_dir: &Path,
 ) -> Result<
     (
-        impl Dataset<Item = (String, u8)>,
-        impl Dataset<Item = (String, u8)>,
+        impl Reiterable<Item = (String, u8)> + Checkpoint + IndexedDataset,
+        impl Reiterable<Item = (String, u8)> + Checkpoint + IndexedDataset,
+    ),
+    Box<dyn Error>,
+> {
+    reviews_with(download_dir, &Downloader::new())
+}
+
+/// reviews_with is `reviews`, downloading with the given `Downloader`.
+#[allow(clippy::type_complexity)]
+pub fn reviews_with(
+    download_dir: &Path,
+    downloader: &Downloader,
+) -> Result<
+    (
+        impl Reiterable<Item = (String, u8)> + Checkpoint + IndexedDataset,
+        impl Reiterable<Item = (String, u8)> + Checkpoint + IndexedDataset,
     ),
     Box<dyn Error>,
 > {
-    download(
-        "http://ai.stanford.edu/~amaas/data/sentiment/aclImdb_v1.tar.gz",
-        download_dir,
-        true,
-    )?;
+    download_reviews(download_dir, downloader)?;
 
     Ok((
         extract_dataset(&download_dir.join("aclImdb").join("train"))?,
@@ -54,20 +83,66 @@ pub fn reviews(
     ))
 }
 
-fn extract_dataset(data_path: &Path) -> Result<impl Dataset<Item = (String, u8)>, Box<dyn Error>> {
-    let positive_dataset = load_directory_dataset(&data_path.join("pos"))?;
-    let negative_dataset = load_directory_dataset(&data_path.join("neg"))?;
-    Ok(positive_dataset.chain(negative_dataset))
+/// Downloads the imdb reviews dataset to the given `download_dir` and loads the given split
+/// from it.
+///
+/// The split is written as in tensorflow datasets, with "train" and "test" as the named splits.
+/// Unlike `reviews`, each named split is shuffled with a fixed seed before it is sliced, so that
+/// a slice like "train[:10%]" has both positive and negative reviews, and is the same every run.
+pub fn reviews_split(
+    download_dir: &Path,
+    split: &str,
+) -> Result<impl Reiterable<Item = (String, u8)> + Checkpoint, Box<dyn Error>> {
+    reviews_split_with(download_dir, split, &Downloader::new())
+}
+
+/// reviews_split_with is `reviews_split`, downloading with the given `Downloader`.
+pub fn reviews_split_with(
+    download_dir: &Path,
+    split: &str,
+    downloader: &Downloader,
+) -> Result<impl Reiterable<Item = (String, u8)> + Checkpoint, Box<dyn Error>> {
+    let split: Split = split.parse()?;
+
+    download_reviews(download_dir, downloader)?;
+
+    split.load(|name| match name {
+        "train" | "test" => {
+            let v: Vec<_> = extract_dataset(&download_dir.join("aclImdb").join(name))?
+                .shuffle_all(SPLIT_SEED)
+                .collect();
+            let len = v.len();
+            Ok((v.into_iter(), len))
+        }
+        _ => Err(format!("unknown imdb reviews split: {:?}", name).into()),
+    })
 }
 
-// TODO: rayon?
-fn load_directory_dataset(dir: &Path) -> Result<impl Dataset<Item = (String, u8)>, Box<dyn Error>> {
-    let dir_reader = dir.read_dir()?;
+/// Checks the imdb reviews archive already downloaded to `download_dir` against its published
+/// checksum, deleting it if it does not match so that the next load downloads it again.
+pub fn verify(download_dir: &Path) -> Result<(), Box<dyn Error>> {
+    utils::verify(download_dir, &[FILE])
+}
 
-    let mut v = Vec::with_capacity(12500);
+fn download_reviews(download_dir: &Path, downloader: &Downloader) -> Result<(), Box<dyn Error>> {
+    let (file_name, checksum) = FILE;
+    downloader.download_from(&MIRRORS, file_name, download_dir, true, Some(checksum))
+}
+
+fn extract_dataset(data_path: &Path) -> Result<vec::IntoIter<(String, u8)>, Box<dyn Error>> {
+    let mut v = Vec::with_capacity(25000);
+    load_directory_dataset(&data_path.join("pos"), &mut v)?;
+    load_directory_dataset(&data_path.join("neg"), &mut v)?;
+    Ok(v.into_iter())
+}
 
-    for entry in dir_reader {
-        let entry = entry?;
+// TODO: rayon?
+fn load_directory_dataset(dir: &Path, v: &mut Vec<(String, u8)>) -> Result<(), Box<dyn Error>> {
+    // NOTE: read_dir has no fixed order, the files are sorted so the order is the same everywhere
+    let mut entries = dir.read_dir()?.collect::<Result<Vec<_>, _>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+
+    for entry in entries {
         let (name, path) = (entry.file_name(), entry.path());
 
         let mut review = String::new();
@@ -75,11 +150,11 @@ fn load_directory_dataset(dir: &Path) -> Result<impl Dataset<Item = (String, u8)
 
         let name = name.into_string().unwrap();
         let rating = name.split('.').next().unwrap();
-        let rating = rating.split('_').last().unwrap();
+        let rating = rating.split('_').next_back().unwrap();
         let rating = rating.parse()?;
 
         v.push((review, rating));
     }
 
-    Ok(v.into_iter())
+    Ok(())
 }
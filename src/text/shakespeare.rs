@@ -5,16 +5,25 @@ use std::fs::File;
 use std::io::Read;
 use std::path::Path;
 
-use crate::utils::download;
+use crate::utils::Downloader;
+
+const MIRRORS: [&str; 1] = ["https://cs.stanford.edu/people/karpathy/char-rnn/"];
 
 /// 100000 characters of shakespeare
 /// http://karpathy.github.io/2015/05/21/rnn-effectiveness/
+///
+/// The text is a plain file from the author's page, which has no published checksum, so it is
+/// not verified after downloading.
 pub fn shakespeare_100000(download_dir: &Path) -> Result<String, Box<dyn Error>> {
-    download(
-        "https://cs.stanford.edu/people/karpathy/char-rnn/shakespear.txt",
-        download_dir,
-        false,
-    )?;
+    shakespeare_100000_with(download_dir, &Downloader::new())
+}
+
+/// shakespeare_100000_with is `shakespeare_100000`, downloading with the given `Downloader`.
+pub fn shakespeare_100000_with(
+    download_dir: &Path,
+    downloader: &Downloader,
+) -> Result<String, Box<dyn Error>> {
+    downloader.download_from(&MIRRORS, "shakespear.txt", download_dir, false, None)?;
 
     let mut f = File::open(download_dir.join("shakespear.txt"))?;
     let mut s = String::new();
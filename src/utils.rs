@@ -2,15 +2,30 @@
 
 use std::borrow::Cow;
 use std::error::Error;
+use std::fmt;
 use std::fs::{self, File};
 use std::io::{self, Read};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
 
 use flate2::read::GzDecoder;
 use indicatif::{HumanBytes, ProgressBar, ProgressStyle};
-use reqwest::{header, Client, Url};
+use reqwest::Url;
+use sha2::{Digest, Sha256};
 use tar::Archive;
 
+mod retry;
+mod transport;
+
+pub use self::retry::RetryPolicy;
+pub use self::transport::{
+    Body, DirectoryTransport, FileInfo, FileTransport, HttpTransport, Transport,
+};
+
+use self::retry::is_transient;
+use self::transport::DefaultTransport;
+
 /// Download/Resume Downloading a file from a HTTP URL to a specific location.
 /// This will return the number of bytes downloaded in its current invocation.
 /// based on: https://github.com/FriedPandaFries/Rust-Examples/blob/master/examples/download.md
@@ -23,100 +38,424 @@ use tar::Archive;
 ///
 /// This function aims to offer same functionality and use-case as `keras.utils.get_file`.
 ///
+/// An interrupted download is resumed if the server supports it and the file has not changed
+/// since, as described in `HttpTransport`, and restarted otherwise.
+///
+/// This is a shorthand for `Downloader::new().download`, use a `Downloader` to fetch files with
+/// a different `Transport`.
+///
 /// TODO: maybe expand this to download more than one file at a time, concurrently.
 /// Right now this may work because most datasets are a single tar or zip.
 pub fn download(url: &str, download_dir: &Path, extract: bool) -> Result<u64, Box<dyn Error>> {
-    // ensure download_dir is a directory
-    if !download_dir.is_dir() {
-        if download_dir.exists() {
-            fs::remove_file(download_dir)?;
+    Downloader::new().download(url, download_dir, extract)
+}
+
+/// download_verified is `download`, checking the downloaded file against `checksum` before
+/// extracting it and marking it completed.
+///
+/// On a mismatch, the file is deleted and a `ChecksumError` is returned, so the next call
+/// downloads it again. Files that were already completed are not checked again, use `verify`
+/// for that.
+pub fn download_verified(
+    url: &str,
+    download_dir: &Path,
+    extract: bool,
+    checksum: Checksum,
+) -> Result<u64, Box<dyn Error>> {
+    Downloader::new().download_verified(url, download_dir, extract, checksum)
+}
+
+/// download_mirrored downloads a file from the first of `mirrors` that serves it, falling back
+/// to the next one on any error, including a mismatch with `checksum`.
+///
+/// This returns the mirror that served the file, or `None` if it was already downloaded. All
+/// mirrors must have the same file name in the final segment of their URL.
+///
+/// ```
+/// use datasets::utils;
+///
+/// let mirror = std::env::temp_dir().join("datasets_mirrors_doctest_mirror");
+/// let download_dir = std::env::temp_dir().join("datasets_mirrors_doctest");
+/// # let _ = std::fs::remove_dir_all(&download_dir);
+/// std::fs::create_dir_all(&mirror).unwrap();
+/// std::fs::write(mirror.join("data.txt"), "hello").unwrap();
+///
+/// let missing = "file:///nonexistent/data.txt".to_string();
+/// let available = format!("file://{}", mirror.join("data.txt").display());
+///
+/// let mirrors = [missing.as_str(), available.as_str()];
+/// let served = utils::download_mirrored(&mirrors, &download_dir, false, None).unwrap();
+/// assert_eq!(served, Some(available.as_str()));
+///
+/// // the next call finds the completed download
+/// let served = utils::download_mirrored(&mirrors, &download_dir, false, None).unwrap();
+/// assert_eq!(served, None);
+/// ```
+pub fn download_mirrored<'a>(
+    mirrors: &[&'a str],
+    download_dir: &Path,
+    extract: bool,
+    checksum: Option<Checksum>,
+) -> Result<Option<&'a str>, Box<dyn Error>> {
+    Downloader::new().download_mirrored(mirrors, download_dir, extract, checksum)
+}
+
+/// A Downloader downloads files with a `Transport`, for the loaders that take one, like
+/// `mnist::load_with`.
+///
+/// `Downloader::new()` fetches HTTP(S) URLs, and `file://` URLs from the local filesystem, which
+/// is what `download` does.
+///
+/// Failed downloads are tried again as given by a `RetryPolicy`, up to 3 times by default,
+/// resuming from what was already downloaded.
+///
+/// ```
+/// use datasets::utils::{Downloader, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let downloader = Downloader::new()
+///     .timeouts(Duration::from_secs(10), Duration::from_secs(60))
+///     .retry(RetryPolicy::new(5).max_backoff(Duration::from_secs(10)));
+/// ```
+pub struct Downloader {
+    // the transport set with `transport`, used instead of the default one
+    transport: Option<Box<dyn Transport>>,
+    // NOTE: kept separately so that the timeouts apply to it whichever is set first
+    default_transport: DefaultTransport,
+    retry: RetryPolicy,
+    verify_checksums: bool,
+}
+
+impl Downloader {
+    /// creates a downloader with the default transport.
+    pub fn new() -> Downloader {
+        Downloader {
+            transport: None,
+            default_transport: DefaultTransport::default(),
+            retry: RetryPolicy::default(),
+            verify_checksums: true,
         }
+    }
+
+    /// sets the policy for retrying failed downloads.
+    pub fn retry(mut self, retry: RetryPolicy) -> Downloader {
+        self.retry = retry;
+        self
+    }
 
-        fs::create_dir_all(download_dir)?;
+    /// sets the connect and read timeouts of HTTP(S) requests, see `HttpTransport::with_timeouts`.
+    ///
+    /// These only apply to the default transport, a transport set with `transport` is used as
+    /// it is, whichever of the two is set first.
+    pub fn timeouts(mut self, connect_timeout: Duration, read_timeout: Duration) -> Downloader {
+        let http = HttpTransport::with_timeouts(connect_timeout, read_timeout);
+        self.default_transport = DefaultTransport::new(http);
+        self
     }
 
-    let u = Url::parse(url)?;
-    let file_name = u.path_segments().and_then(|s| s.last()).unwrap();
+    /// sets whether the loaders check the files they download against their published
+    /// checksums, which they do by default.
+    ///
+    /// Turning this off is meant for serving small fixtures in place of the published files,
+    /// for example with a `DirectoryTransport` in tests. A checksum passed to
+    /// `download_verified` or `download_mirrored` is always checked.
+    ///
+    /// ```
+    /// use datasets::utils::{DirectoryTransport, Downloader};
+    ///
+    /// let fixtures = std::env::temp_dir().join("datasets_fixtures");
+    /// let downloader = Downloader::new()
+    ///     .transport(DirectoryTransport::new(&fixtures))
+    ///     .verify_checksums(false);
+    /// ```
+    pub fn verify_checksums(mut self, verify: bool) -> Downloader {
+        self.verify_checksums = verify;
+        self
+    }
 
-    let completed_file_location = download_dir.join(format!("{}.completed", file_name));
-    if completed_file_location.exists() {
-        eprintln!(
-            "Already downloaded {}from {}",
-            if extract { "and extracted " } else { "" },
-            url
-        );
+    /// sets the transport used to fetch files.
+    pub fn transport<T>(mut self, transport: T) -> Downloader
+    where
+        T: Transport + 'static,
+    {
+        self.transport = Some(Box::new(transport));
+        self
+    }
 
-        return Ok(0);
+    // the transport set with `transport`, or else the default one
+    fn get_transport(&self) -> &dyn Transport {
+        match self.transport {
+            Some(ref transport) => transport.as_ref(),
+            None => &self.default_transport,
+        }
     }
 
-    let client = Client::new();
+    /// download is `utils::download` with this downloader's transport.
+    pub fn download(
+        &self,
+        url: &str,
+        download_dir: &Path,
+        extract: bool,
+    ) -> Result<u64, Box<dyn Error>> {
+        self.download_file(url, download_dir, extract, None)
+    }
 
-    // (try to) get the total size of the download
-    let total_size: Option<u64> = {
-        let size_resp = client.head(url).send()?;
-        if size_resp.status().is_success() {
-            size_resp
-                .headers()
-                .get(header::CONTENT_LENGTH)
-                .and_then(|l| l.to_str().ok())
-                .and_then(|l| l.parse().ok())
-        } else {
-            None
+    /// download_verified is `utils::download_verified` with this downloader's transport.
+    pub fn download_verified(
+        &self,
+        url: &str,
+        download_dir: &Path,
+        extract: bool,
+        checksum: Checksum,
+    ) -> Result<u64, Box<dyn Error>> {
+        self.download_file(url, download_dir, extract, Some(checksum))
+    }
+
+    /// download_mirrored is `utils::download_mirrored` with this downloader's transport.
+    pub fn download_mirrored<'a>(
+        &self,
+        mirrors: &[&'a str],
+        download_dir: &Path,
+        extract: bool,
+        checksum: Option<Checksum>,
+    ) -> Result<Option<&'a str>, Box<dyn Error>> {
+        let first = mirrors
+            .first()
+            .ok_or("download_mirrored: no mirrors given")?;
+
+        let file_name = file_name(&Url::parse(first)?).to_string();
+        if download_dir
+            .join(format!("{}.completed", file_name))
+            .exists()
+        {
+            eprintln!(
+                "Already downloaded {}{}",
+                if extract { "and extracted " } else { "" },
+                file_name
+            );
+
+            return Ok(None);
         }
-    };
 
-    let mut req = client.get(url);
-    let location = download_dir.join(file_name);
-    let mut downloaded = 0;
+        let mut last_err = None;
 
-    if location.exists() {
-        if total_size.is_some() {
-            // resume an in progress download
-            downloaded = location.metadata()?.len();
-            req = req.header(header::RANGE, format!("bytes={}-", downloaded - 1));
-        } else {
-            fs::remove_file(&location)?;
+        for url in mirrors {
+            match self.download_file(url, download_dir, extract, checksum) {
+                Ok(_) => {
+                    eprintln!("Downloaded {} from mirror {}", file_name, url);
+                    return Ok(Some(url));
+                }
+                Err(err) => {
+                    eprintln!("Unable to download from {}: {}", url, err);
+                    last_err = Some(err);
+                }
+            }
         }
-    }
 
-    if total_size.is_some() && downloaded == total_size.unwrap() {
-        eprintln!("Skipping Downloading {}, it is already done", url);
-    } else {
-        let mut reader = DownloadWrapper::new(req.send()?, total_size, downloaded);
+        Err(format!(
+            "unable to download {} from any of {} mirrors, the last error was: {}",
+            file_name,
+            mirrors.len(),
+            last_err.unwrap()
+        )
+        .into())
+    }
 
-        // TODO: do this within DownloadWrapper
-        reader.progress.println(format!("Downloading from {}", url));
+    // downloads `file_name` from the first of the `mirrors` base URLs that serves it, for the
+    // loaders
+    pub(crate) fn download_from(
+        &self,
+        mirrors: &[&str],
+        file_name: &str,
+        download_dir: &Path,
+        extract: bool,
+        checksum: Option<Checksum>,
+    ) -> Result<(), Box<dyn Error>> {
+        let urls: Vec<String> = mirrors
+            .iter()
+            .map(|mirror| format!("{}{}", mirror, file_name))
+            .collect();
+        let urls: Vec<&str> = urls.iter().map(String::as_str).collect();
+        let checksum = checksum.filter(|_| self.verify_checksums);
 
-        let mut writer = fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&location)?;
+        self.download_mirrored(&urls, download_dir, extract, checksum)?;
+        Ok(())
+    }
 
-        io::copy(&mut reader, &mut writer)?;
+    fn download_file(
+        &self,
+        url: &str,
+        download_dir: &Path,
+        extract: bool,
+        checksum: Option<Checksum>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let mut retry = 0;
 
-        // TODO: do this within DownloadWrapper
-        // NOTE: finish_with_message not printing the message.
-        // hence finish + eprintln
-        reader.progress.finish();
-        eprintln!(
-            "Downloaded {} to {}",
-            HumanBytes(reader.downloaded),
-            location.to_string_lossy()
-        );
+        loop {
+            // NOTE: an interrupted download is left in place, so the next attempt resumes it
+            match self.try_download_file(url, download_dir, extract, checksum) {
+                Err(err) if retry + 1 < self.retry.max_attempts() && is_transient(err.as_ref()) => {
+                    let delay = self.retry.delay(retry);
+                    eprintln!(
+                        "Unable to download from {}: {}, retrying in {:.1}s",
+                        url,
+                        err,
+                        delay.as_secs_f64()
+                    );
 
-        downloaded = reader.downloaded;
+                    thread::sleep(delay);
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
     }
 
-    if extract {
-        if decompress(&location)? {
-            eprintln!("Successfully decompressed {:?}", location);
+    fn try_download_file(
+        &self,
+        url: &str,
+        download_dir: &Path,
+        extract: bool,
+        checksum: Option<Checksum>,
+    ) -> Result<u64, Box<dyn Error>> {
+        // ensure download_dir is a directory
+        if !download_dir.is_dir() {
+            if download_dir.exists() {
+                fs::remove_file(download_dir)?;
+            }
+
+            fs::create_dir_all(download_dir)?;
+        }
+
+        let u = Url::parse(url)?;
+        let file_name = file_name(&u);
+
+        let completed_file_location = download_dir.join(format!("{}.completed", file_name));
+        if completed_file_location.exists() {
+            eprintln!(
+                "Already downloaded {}from {}",
+                if extract { "and extracted " } else { "" },
+                url
+            );
+
+            return Ok(0);
+        }
+
+        // (try to) get the total size of the download, and whether it can be resumed
+        let info = self.get_transport().info(&u)?;
+
+        let location = download_dir.join(file_name);
+        let validator_location = download_dir.join(format!("{}.validator", file_name));
+        let mut offset = 0;
+
+        if location.exists() {
+            // NOTE: the validator of the file when its download started is saved next to it,
+            // empty if there was none, so only the same version of the file is resumed
+            let started_validator = fs::read_to_string(&validator_location)
+                .ok()
+                .map(|v| Some(v).filter(|v| !v.is_empty()));
+
+            let partial = location.metadata()?.len();
+            let resumable = info.resumable
+                && started_validator == Some(info.validator.clone())
+                && info.size.is_some_and(|size| partial <= size);
+
+            if resumable {
+                // resume an in progress download
+                offset = partial;
+            } else {
+                fs::remove_file(&location)?;
+            }
+        }
+
+        let mut downloaded = 0;
+
+        if offset > 0 && info.size == Some(offset) {
+            eprintln!("Skipping Downloading {}, it is already done", url);
         } else {
-            eprintln!("Unable to decompress {:?}", location);
+            fs::write(&validator_location, info.validator.as_deref().unwrap_or(""))?;
+
+            let body = self
+                .get_transport()
+                .open(&u, offset, info.validator.as_deref())?;
+
+            let mut writer = if body.offset == 0 {
+                if offset > 0 {
+                    eprintln!("Unable to resume downloading {}, restarting", url);
+                }
+
+                File::create(&location)?
+            } else if body.offset == offset {
+                fs::OpenOptions::new().append(true).open(&location)?
+            } else {
+                return Err(format!(
+                    "{} was opened from byte {} instead of {}",
+                    url, body.offset, offset
+                )
+                .into());
+            };
+
+            let mut reader = DownloadWrapper::new(body.reader, info.size, body.offset);
+
+            // TODO: do this within DownloadWrapper
+            reader.progress.println(format!("Downloading from {}", url));
+
+            io::copy(&mut reader, &mut writer)?;
+
+            // TODO: do this within DownloadWrapper
+            // NOTE: finish_with_message not printing the message.
+            // hence finish + eprintln
+            reader.progress.finish();
+            eprintln!(
+                "Downloaded {} to {}",
+                HumanBytes(reader.downloaded),
+                location.to_string_lossy()
+            );
+
+            downloaded = reader.downloaded;
+        }
+
+        if let Some(checksum) = checksum {
+            if let Err(err) = checksum.check(&location) {
+                fs::remove_file(&location)?;
+                let _ = fs::remove_file(&validator_location);
+                return Err(err);
+            }
         }
+
+        if extract {
+            if decompress(&location)? {
+                eprintln!("Successfully decompressed {:?}", location);
+            } else {
+                eprintln!("Unable to decompress {:?}", location);
+            }
+        }
+
+        let _ = fs::remove_file(&validator_location);
+        File::create(completed_file_location)?;
+        Ok(downloaded)
+    }
+}
+
+impl Default for Downloader {
+    fn default() -> Downloader {
+        Downloader::new()
     }
+}
+
+impl fmt::Debug for Downloader {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Downloader {{ retry: {:?}, verify_checksums: {} }}",
+            self.retry, self.verify_checksums
+        )
+    }
+}
 
-    File::create(completed_file_location)?;
-    Ok(downloaded)
+// the name of the file at `url`, the final segment of its path
+fn file_name(url: &Url) -> &str {
+    url.path_segments().and_then(|mut s| s.next_back()).unwrap()
 }
 
 struct DownloadWrapper<R> {
@@ -164,14 +503,163 @@ where
     R: Read,
 {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.reader.read(buf).map(|n| {
+        self.reader.read(buf).inspect(|&n| {
             self.downloaded += n as u64;
             self.progress.inc(n as u64);
-            n
         })
     }
 }
 
+/// Checksum is the expected SHA-256 digest, and optionally the size, of a downloaded file.
+///
+/// ```
+/// use datasets::utils::Checksum;
+///
+/// let path = std::env::temp_dir().join("datasets_checksum_doctest");
+/// std::fs::write(&path, "hello").unwrap();
+///
+/// let checksum = Checksum {
+///     sha256: "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824",
+///     size: Some(5),
+/// };
+/// assert!(checksum.check(&path).is_ok());
+///
+/// let checksum = Checksum { size: Some(6), ..checksum };
+/// assert!(checksum.check(&path).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checksum<'a> {
+    /// the SHA-256 digest of the file, as a hex string
+    pub sha256: &'a str,
+    /// the size of the file in bytes, checked before computing the digest if present
+    pub size: Option<u64>,
+}
+
+impl<'a> Checksum<'a> {
+    /// checks the file at `path`, returning a `ChecksumError` if it does not match.
+    pub fn check(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(size) = self.size {
+            let actual = path.metadata()?.len();
+            if actual != size {
+                return Err(Box::new(ChecksumError::Size {
+                    path: path.to_path_buf(),
+                    expected: size,
+                    actual,
+                }));
+            }
+        }
+
+        let mut hasher = Sha256::new();
+        io::copy(&mut File::open(path)?, &mut hasher)?;
+        let actual: String = hasher
+            .finalize()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+
+        if !actual.eq_ignore_ascii_case(self.sha256) {
+            return Err(Box::new(ChecksumError::Sha256 {
+                path: path.to_path_buf(),
+                expected: self.sha256.to_string(),
+                actual,
+            }));
+        }
+
+        Ok(())
+    }
+}
+
+/// ChecksumError is returned when a file does not match its `Checksum`.
+#[derive(Debug)]
+pub enum ChecksumError {
+    /// the file has a different size
+    Size {
+        /// the checked file
+        path: PathBuf,
+        /// the expected size in bytes
+        expected: u64,
+        /// the actual size in bytes
+        actual: u64,
+    },
+    /// the file has a different SHA-256 digest
+    Sha256 {
+        /// the checked file
+        path: PathBuf,
+        /// the expected digest
+        expected: String,
+        /// the actual digest
+        actual: String,
+    },
+}
+
+impl fmt::Display for ChecksumError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChecksumError::Size {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "checksum mismatch for {:?}: expected {} bytes, got {} bytes",
+                path, expected, actual
+            ),
+            ChecksumError::Sha256 {
+                path,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "checksum mismatch for {:?}: expected sha256 {}, got {}",
+                path, expected, actual
+            ),
+        }
+    }
+}
+
+impl Error for ChecksumError {}
+
+/// verify checks the files downloaded to `download_dir` against their checksums, given by file
+/// name.
+///
+/// Files that do not match, or are missing, are deleted along with their completed markers, so
+/// they are downloaded again on the next `download`. The first error is returned after all
+/// files are checked.
+pub fn verify(download_dir: &Path, files: &[(&str, Checksum)]) -> Result<(), Box<dyn Error>> {
+    let mut result = Ok(());
+
+    for (file_name, checksum) in files {
+        let location = download_dir.join(file_name);
+        if let Err(err) = checksum.check(&location) {
+            eprintln!("Unable to verify {:?}: {}", location, err);
+
+            let _ = fs::remove_file(&location);
+            let _ = fs::remove_file(download_dir.join(format!("{}.completed", file_name)));
+
+            if result.is_ok() {
+                result = Err(err);
+            }
+        }
+    }
+
+    result
+}
+
+/// download_async is `download` for async code, available with the `stream` feature.
+///
+/// reqwest's async client is not compatible with `std::future`, so the download runs on a
+/// background thread and the returned future completes when it is done. Errors are returned
+/// with their message only.
+#[cfg(feature = "stream")]
+pub fn download_async(
+    url: &str,
+    download_dir: &Path,
+    extract: bool,
+) -> crate::stream::Blocking<Result<u64, Box<dyn Error + Send + Sync>>> {
+    let (url, download_dir) = (url.to_string(), download_dir.to_path_buf());
+    crate::stream::load(move || download(&url, &download_dir, extract))
+}
+
 fn decompress(path: &Path) -> Result<bool, Box<dyn Error>> {
     let p = path.to_string_lossy();
 
@@ -0,0 +1,183 @@
+use std::error::Error;
+use std::io;
+use std::time::Duration;
+
+use rand::Rng;
+
+/// A RetryPolicy decides how often, and after how long, a `Downloader` tries a failed download
+/// again.
+///
+/// The delay before each retry doubles from `initial_backoff` up to `max_backoff`, and with
+/// jitter, a uniformly random delay up to that is used instead, so that many clients do not
+/// retry at the same time.
+///
+/// ```
+/// use datasets::utils::RetryPolicy;
+/// use std::time::Duration;
+///
+/// let policy = RetryPolicy::new(5)
+///     .initial_backoff(Duration::from_millis(100))
+///     .max_backoff(Duration::from_secs(1))
+///     .jitter(false);
+///
+/// assert_eq!(policy.backoff(0), Duration::from_millis(100));
+/// assert_eq!(policy.backoff(2), Duration::from_millis(400));
+/// assert_eq!(policy.backoff(10), Duration::from_secs(1));
+/// ```
+///
+/// Only transient errors are retried, like connection failures, timeouts and server errors.
+/// Missing files, client errors and checksum mismatches fail right away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: usize,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    jitter: bool,
+}
+
+impl RetryPolicy {
+    /// creates a policy that tries a download up to `max_attempts` times, including the first.
+    ///
+    /// This will panic if max_attempts is 0.
+    pub fn new(max_attempts: usize) -> RetryPolicy {
+        assert!(
+            max_attempts > 0,
+            "RetryPolicy: max_attempts must be greater than 0"
+        );
+
+        RetryPolicy {
+            max_attempts,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(30),
+            jitter: true,
+        }
+    }
+
+    /// creates a policy that never retries.
+    pub fn never() -> RetryPolicy {
+        RetryPolicy::new(1)
+    }
+
+    /// the delay before the first retry.
+    ///
+    /// defaults to 1 second.
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> RetryPolicy {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// the longest delay between retries.
+    ///
+    /// defaults to 30 seconds.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> RetryPolicy {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// when set to false, the delays are exactly as given by `backoff`.
+    ///
+    /// defaults to true.
+    pub fn jitter(mut self, jitter: bool) -> RetryPolicy {
+        self.jitter = jitter;
+        self
+    }
+
+    /// the number of times a download is tried, including the first.
+    pub fn max_attempts(&self) -> usize {
+        self.max_attempts
+    }
+
+    /// the delay before the retry after `retry` previous retries, without jitter.
+    pub fn backoff(&self, retry: usize) -> Duration {
+        // NOTE: a shift of 32 or more overflows any backoff worth waiting for
+        let factor = 1u32.checked_shl(retry as u32).unwrap_or(u32::MAX);
+        self.initial_backoff
+            .checked_mul(factor)
+            .unwrap_or(self.max_backoff)
+            .min(self.max_backoff)
+    }
+
+    // the delay before the retry after `retry` previous retries, with jitter if enabled
+    pub(crate) fn delay(&self, retry: usize) -> Duration {
+        let backoff = self.backoff(retry);
+        if !self.jitter {
+            return backoff;
+        }
+
+        let nanos = backoff.as_nanos().min(u128::from(u64::MAX)) as u64;
+        Duration::from_nanos(rand::thread_rng().gen_range(0, nanos.saturating_add(1)))
+    }
+}
+
+impl Default for RetryPolicy {
+    /// tries a download up to 3 times.
+    fn default() -> RetryPolicy {
+        RetryPolicy::new(3)
+    }
+}
+
+// whether a failed download may succeed if it is tried again
+pub(crate) fn is_transient(err: &(dyn Error + 'static)) -> bool {
+    if let Some(err) = err.downcast_ref::<reqwest::Error>() {
+        return match err.status() {
+            Some(status) => status.is_server_error() || status.as_u16() == 429,
+            None => err.is_http() || err.is_timeout(),
+        };
+    }
+
+    if let Some(err) = err.downcast_ref::<io::Error>() {
+        // NOTE: reading a response body reports its reqwest errors as io errors wrapping them
+        if let Some(inner) = err.get_ref() {
+            if inner.is::<reqwest::Error>() {
+                return is_transient(inner);
+            }
+        }
+
+        return matches!(
+            err.kind(),
+            io::ErrorKind::TimedOut
+                | io::ErrorKind::Interrupted
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+                | io::ErrorKind::BrokenPipe
+                | io::ErrorKind::UnexpectedEof
+        );
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // the error of a request that cannot be sent
+    fn request_error(url: &str) -> reqwest::Error {
+        reqwest::Client::new().get(url).send().unwrap_err()
+    }
+
+    #[test]
+    fn io_errors() {
+        let transient = io::Error::new(io::ErrorKind::ConnectionReset, "reset");
+        assert!(is_transient(&transient));
+        let permanent = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        assert!(!is_transient(&permanent));
+    }
+
+    #[test]
+    fn reqwest_errors() {
+        // nothing listens on port 1, so connecting is refused
+        assert!(is_transient(&request_error("http://127.0.0.1:1/data.txt")));
+        assert!(!is_transient(&request_error("http://[::1/data.txt")));
+    }
+
+    #[test]
+    fn reqwest_errors_wrapped_in_io_errors() {
+        let wrapped = |err| io::Error::other(err);
+
+        let refused = wrapped(request_error("http://127.0.0.1:1/data.txt"));
+        assert!(is_transient(&refused));
+        let invalid_url = wrapped(request_error("http://[::1/data.txt"));
+        assert!(!is_transient(&invalid_url));
+    }
+}
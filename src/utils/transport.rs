@@ -0,0 +1,301 @@
+use std::error::Error;
+use std::fmt;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use reqwest::{header, Client, StatusCode, Url};
+
+/// A Transport fetches the files downloaded by a `Downloader`.
+///
+/// The default transport of a `Downloader` fetches HTTP(S) URLs with reqwest and `file://` URLs
+/// from the local filesystem. A custom transport can fetch from anywhere else, for example an
+/// internal artifact store, or serve fixtures in tests.
+pub trait Transport: Send + Sync {
+    /// returns what is known about the file at `url` before downloading it.
+    fn info(&self, url: &Url) -> Result<FileInfo, Box<dyn Error>>;
+
+    /// opens the file at `url` for reading, starting at byte `offset` if possible.
+    ///
+    /// `validator` is the validator of the file when its download started. If the file has
+    /// changed since, or cannot be read from `offset`, the whole file is returned instead, with
+    /// an offset of 0.
+    fn open(&self, url: &Url, offset: u64, validator: Option<&str>)
+        -> Result<Body, Box<dyn Error>>;
+}
+
+/// FileInfo is what a `Transport` knows about a file before downloading it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileInfo {
+    /// the size of the file in bytes, if known
+    pub size: Option<u64>,
+    /// whether the file can be read from an offset, to resume a download
+    pub resumable: bool,
+    /// identifies the version of the file, like an HTTP ETag or Last-Modified date, so that a
+    /// download is only resumed if the file has not changed
+    pub validator: Option<String>,
+}
+
+/// Body is a file opened by a `Transport`.
+pub struct Body {
+    /// the contents of the file, from `offset`
+    pub reader: Box<dyn Read>,
+    /// the position in the file of the first byte of `reader`
+    pub offset: u64,
+}
+
+impl fmt::Debug for Body {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Body {{ offset: {} }}", self.offset)
+    }
+}
+
+/// HttpTransport fetches HTTP(S) URLs with a `reqwest::Client`.
+///
+/// Downloads are resumed with a `Range` request if the server sends `Accept-Ranges: bytes`, and
+/// the `ETag`, or else the `Last-Modified` date, of the file is sent in `If-Range`, so that the
+/// server returns the whole file if it has changed. A server that ignores the range, and
+/// answers with `200 OK` instead of `206 Partial Content`, restarts the download.
+///
+/// ```
+/// use datasets::utils::{Downloader, HttpTransport};
+/// use std::time::Duration;
+///
+/// let http = HttpTransport::with_timeouts(Duration::from_secs(10), Duration::from_secs(60));
+/// let downloader = Downloader::new().transport(http);
+/// ```
+#[derive(Debug)]
+pub struct HttpTransport {
+    client: Client,
+}
+
+impl HttpTransport {
+    /// creates a transport with a default `reqwest::Client`.
+    pub fn new() -> HttpTransport {
+        HttpTransport::with_client(Client::new())
+    }
+
+    /// creates a transport with a configured `reqwest::Client`, for example with proxies or
+    /// extra headers.
+    pub fn with_client(client: Client) -> HttpTransport {
+        HttpTransport { client }
+    }
+
+    /// creates a transport that gives up connecting after `connect_timeout`, and reading, or
+    /// writing, after `read_timeout` without progress.
+    ///
+    /// This will panic if the TLS backend cannot be initialized, like `reqwest::Client::new`.
+    pub fn with_timeouts(connect_timeout: Duration, read_timeout: Duration) -> HttpTransport {
+        let client = Client::builder()
+            .connect_timeout(connect_timeout)
+            .timeout(read_timeout)
+            .build()
+            .expect("HttpTransport: unable to build a reqwest::Client");
+
+        HttpTransport::with_client(client)
+    }
+}
+
+impl Default for HttpTransport {
+    fn default() -> HttpTransport {
+        HttpTransport::new()
+    }
+}
+
+impl Transport for HttpTransport {
+    fn info(&self, url: &Url) -> Result<FileInfo, Box<dyn Error>> {
+        let resp = self.client.head(url.clone()).send()?;
+        if !resp.status().is_success() {
+            return Ok(FileInfo::default());
+        }
+
+        let headers = resp.headers();
+        let get = |name| headers.get(name).and_then(|v| v.to_str().ok());
+
+        // NOTE: a weak ETag cannot be used in If-Range
+        let validator = get(header::ETAG)
+            .filter(|etag| !etag.starts_with("W/"))
+            .or_else(|| get(header::LAST_MODIFIED))
+            .map(String::from);
+
+        Ok(FileInfo {
+            size: get(header::CONTENT_LENGTH).and_then(|l| l.parse().ok()),
+            resumable: get(header::ACCEPT_RANGES).is_some_and(|r| r.contains("bytes")),
+            validator,
+        })
+    }
+
+    fn open(
+        &self,
+        url: &Url,
+        offset: u64,
+        validator: Option<&str>,
+    ) -> Result<Body, Box<dyn Error>> {
+        let mut req = self.client.get(url.clone());
+        if offset > 0 {
+            req = req.header(header::RANGE, format!("bytes={}-", offset));
+            if let Some(validator) = validator {
+                req = req.header(header::IF_RANGE, validator);
+            }
+        }
+
+        let resp = req.send()?.error_for_status()?;
+        if resp.status() != StatusCode::PARTIAL_CONTENT {
+            return Ok(Body {
+                reader: Box::new(resp),
+                offset: 0,
+            });
+        }
+
+        // Content-Range: bytes {start}-{end}/{size}
+        let start = resp
+            .headers()
+            .get(header::CONTENT_RANGE)
+            .and_then(|r| r.to_str().ok())
+            .and_then(|r| r.strip_prefix("bytes "))
+            .and_then(|r| r.split('-').next())
+            .and_then(|start| start.parse::<u64>().ok());
+
+        match start {
+            Some(start) if start == offset => Ok(Body {
+                reader: Box::new(resp),
+                offset,
+            }),
+            _ => Err(format!(
+                "unexpected Content-Range for {} from byte {}: {:?}",
+                url,
+                offset,
+                resp.headers().get(header::CONTENT_RANGE)
+            )
+            .into()),
+        }
+    }
+}
+
+/// FileTransport fetches `file://` URLs from the local filesystem.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct FileTransport;
+
+impl FileTransport {
+    fn path(url: &Url) -> Result<PathBuf, Box<dyn Error>> {
+        url.to_file_path()
+            .map_err(|_| format!("not a local file URL: {}", url).into())
+    }
+}
+
+impl Transport for FileTransport {
+    fn info(&self, url: &Url) -> Result<FileInfo, Box<dyn Error>> {
+        file_info(&FileTransport::path(url)?)
+    }
+
+    fn open(&self, url: &Url, offset: u64, _: Option<&str>) -> Result<Body, Box<dyn Error>> {
+        open_file(&FileTransport::path(url)?, offset)
+    }
+}
+
+/// DirectoryTransport serves every URL from a local directory, by the final segment of the URL,
+/// like a local mirror.
+///
+/// ```
+/// use datasets::utils::{DirectoryTransport, Downloader};
+///
+/// let mirror = std::env::temp_dir().join("datasets_transport_doctest_mirror");
+/// let download_dir = std::env::temp_dir().join("datasets_transport_doctest");
+/// # let _ = std::fs::remove_dir_all(&download_dir);
+/// std::fs::create_dir_all(&mirror).unwrap();
+/// std::fs::write(mirror.join("data.txt"), "hello").unwrap();
+///
+/// let downloader = Downloader::new().transport(DirectoryTransport::new(&mirror));
+/// downloader
+///     .download("https://example.com/files/data.txt", &download_dir, false)
+///     .unwrap();
+///
+/// let data = std::fs::read_to_string(download_dir.join("data.txt")).unwrap();
+/// assert_eq!(data, "hello");
+/// ```
+#[derive(Debug, Clone)]
+pub struct DirectoryTransport {
+    dir: PathBuf,
+}
+
+impl DirectoryTransport {
+    /// creates a transport that serves files from `dir`.
+    pub fn new(dir: &Path) -> DirectoryTransport {
+        DirectoryTransport {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    fn path(&self, url: &Url) -> Result<PathBuf, Box<dyn Error>> {
+        let file_name = url
+            .path_segments()
+            .and_then(|mut s| s.next_back())
+            .filter(|s| !s.is_empty())
+            .ok_or_else(|| format!("no file name in URL: {}", url))?;
+
+        Ok(self.dir.join(file_name))
+    }
+}
+
+impl Transport for DirectoryTransport {
+    fn info(&self, url: &Url) -> Result<FileInfo, Box<dyn Error>> {
+        file_info(&self.path(url)?)
+    }
+
+    fn open(&self, url: &Url, offset: u64, _: Option<&str>) -> Result<Body, Box<dyn Error>> {
+        open_file(&self.path(url)?, offset)
+    }
+}
+
+/// DefaultTransport is the transport of `Downloader::new`, fetching `file://` URLs with a
+/// `FileTransport` and everything else with an `HttpTransport`.
+#[derive(Debug, Default)]
+pub(crate) struct DefaultTransport {
+    http: HttpTransport,
+}
+
+impl DefaultTransport {
+    pub(crate) fn new(http: HttpTransport) -> DefaultTransport {
+        DefaultTransport { http }
+    }
+}
+
+impl Transport for DefaultTransport {
+    fn info(&self, url: &Url) -> Result<FileInfo, Box<dyn Error>> {
+        match url.scheme() {
+            "file" => FileTransport.info(url),
+            _ => self.http.info(url),
+        }
+    }
+
+    fn open(
+        &self,
+        url: &Url,
+        offset: u64,
+        validator: Option<&str>,
+    ) -> Result<Body, Box<dyn Error>> {
+        match url.scheme() {
+            "file" => FileTransport.open(url, offset, validator),
+            _ => self.http.open(url, offset, validator),
+        }
+    }
+}
+
+// local files can always be resumed, and have no validator, only their size is checked
+fn file_info(path: &Path) -> Result<FileInfo, Box<dyn Error>> {
+    Ok(FileInfo {
+        size: Some(path.metadata()?.len()),
+        resumable: true,
+        validator: None,
+    })
+}
+
+fn open_file(path: &Path, offset: u64) -> Result<Body, Box<dyn Error>> {
+    let mut f = File::open(path)?;
+    f.seek(SeekFrom::Start(offset))?;
+    Ok(Body {
+        reader: Box::new(f),
+        offset,
+    })
+}
@@ -0,0 +1,129 @@
+//! Restoring checkpoints of adapters at every position of a dataset.
+
+use std::fmt::Debug;
+
+use datasets::{choose_from_datasets, sample_from_datasets, Checkpoint, Dataset};
+
+// checks that restoring the state saved after every element continues with the same elements
+// as the uninterrupted dataset
+fn check_restore<D, F>(make: F)
+where
+    D: Iterator + Checkpoint,
+    D::Item: PartialEq + Debug,
+    F: Fn() -> D,
+{
+    let all: Vec<D::Item> = make().collect();
+
+    for n in 0..=all.len() {
+        let mut dataset = make();
+        dataset.by_ref().take(n).for_each(drop);
+
+        let mut state = Vec::new();
+        dataset.save_state(&mut state).unwrap();
+
+        let mut restored = make();
+        restored.restore_state(&mut &state[..]).unwrap();
+
+        let rest: Vec<D::Item> = restored.collect();
+        assert_eq!(rest[..], all[n..], "restored after {} elements", n);
+    }
+}
+
+fn nested() -> Vec<Vec<usize>> {
+    vec![
+        vec![1, 2, 3],
+        vec![4, 5],
+        vec![6],
+        vec![7, 8, 9, 10],
+        vec![],
+        vec![11],
+    ]
+}
+
+#[test]
+fn interleave() {
+    check_restore(|| nested().into_iter().interleave(2, 1));
+    check_restore(|| nested().into_iter().interleave(3, 2));
+}
+
+#[test]
+fn interleave_repeated() {
+    check_restore(|| nested().into_iter().interleave(2, 1).repeat(2));
+}
+
+#[test]
+fn sample_from_datasets_restores_rng() {
+    let datasets = || {
+        vec![
+            (0..10).collect::<Vec<_>>().into_iter(),
+            (100..105).collect::<Vec<_>>().into_iter(),
+        ]
+    };
+
+    check_restore(|| sample_from_datasets(datasets(), vec![0.5, 0.5], 7, false));
+    check_restore(|| sample_from_datasets(datasets(), vec![0.5, 0.5], 7, true));
+}
+
+#[test]
+fn choose_from_datasets_restores_selector() {
+    let datasets = || {
+        vec![
+            (0..3).collect::<Vec<_>>().into_iter(),
+            (100..105).collect::<Vec<_>>().into_iter(),
+        ]
+    };
+    let selector = || vec![0, 1, 1, 0, 1, 0, 1, 1, 1, 0].into_iter();
+
+    check_restore(|| choose_from_datasets(datasets(), selector(), false));
+    check_restore(|| choose_from_datasets(datasets(), selector(), true));
+}
+
+#[test]
+fn checkpointable_map_and_filter() {
+    check_restore(|| {
+        (0..20)
+            .checkpointable_map(|x| x * 3)
+            .checkpointable_filter(|x| x % 2 == 0)
+            .shuffle(4, 0)
+            .batch(3, false)
+    });
+}
+
+#[test]
+fn cache_restores_while_filling_and_replaying() {
+    check_restore(|| (0..6).checkpointable_map(|x| x * 2).cache().repeat(3));
+}
+
+#[test]
+fn cache_to_restores_while_writing_and_reading() {
+    let path = std::env::temp_dir().join("datasets_checkpoint_cache_to");
+    let completed = std::env::temp_dir().join("datasets_checkpoint_cache_to.completed");
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(&completed);
+
+    // the first epoch writes the cache, and the others read it
+    check_restore(|| (0..6).cache_to(&path).repeat(3));
+}
+
+#[test]
+fn cache_to_restored_while_writing_is_written_again() {
+    let path = std::env::temp_dir().join("datasets_checkpoint_cache_to_writing");
+    let completed = std::env::temp_dir().join("datasets_checkpoint_cache_to_writing.completed");
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(&completed);
+
+    let mut dataset = (0..6).cache_to(&path);
+    dataset.by_ref().take(2).for_each(drop);
+
+    let mut state = Vec::new();
+    dataset.save_state(&mut state).unwrap();
+
+    let mut restored = (0..6).cache_to(&path);
+    restored.restore_state(&mut &state[..]).unwrap();
+    assert_eq!(restored.collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+    assert!(!completed.exists());
+
+    let all: Vec<usize> = (0..6).cache_to(&path).collect();
+    assert_eq!(all, vec![0, 1, 2, 3, 4, 5]);
+    assert!(completed.exists());
+}
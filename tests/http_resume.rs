@@ -0,0 +1,187 @@
+//! Resuming HTTP downloads against a stand-in server, which serves "0123456789" and cuts off
+//! its first response after 5 bytes.
+
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use datasets::utils::{Downloader, RetryPolicy};
+
+const DATA: &str = "0123456789";
+
+struct Server {
+    accept_ranges: bool,
+    honor_ranges: bool,
+    change_etag: bool,
+}
+
+impl Server {
+    // starts the server on a free port, returning the URL of its file, and the Range header of
+    // every GET request it receives
+    fn start(self) -> (String, Arc<Mutex<Vec<Option<String>>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/data.txt", listener.local_addr().unwrap());
+        let ranges = Arc::new(Mutex::new(Vec::new()));
+        let received = Arc::clone(&ranges);
+
+        thread::spawn(move || {
+            let mut gets = 0;
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut lines = BufReader::new(stream.try_clone().unwrap()).lines();
+                let method = lines.next().unwrap().unwrap();
+
+                let (mut start, mut range, mut if_range) = (0, None, String::new());
+                for line in lines.map(|l| l.unwrap()).take_while(|l| !l.is_empty()) {
+                    let (name, value) = line.split_at(line.find(':').unwrap());
+                    let value = value[1..].trim();
+                    match name.to_lowercase().as_str() {
+                        // "bytes={start}-"
+                        "range" => {
+                            start = value[6..value.len() - 1].parse().unwrap();
+                            range = Some(value.to_string());
+                        }
+                        "if-range" => if_range = value.to_string(),
+                        _ => {}
+                    }
+                }
+
+                let etag = if self.change_etag && gets > 0 {
+                    "\"v2\""
+                } else {
+                    "\"v1\""
+                };
+
+                let mut head = format!("ETag: {}\r\nConnection: close\r\n", etag);
+                if self.accept_ranges {
+                    head += "Accept-Ranges: bytes\r\n";
+                }
+
+                let response = if method.starts_with("HEAD") {
+                    format!("HTTP/1.1 200 OK\r\n{}Content-Length: 10\r\n\r\n", head)
+                } else {
+                    received.lock().unwrap().push(range);
+                    gets += 1;
+                    if gets == 1 {
+                        format!(
+                            "HTTP/1.1 200 OK\r\n{}Content-Length: 10\r\n\r\n{}",
+                            head,
+                            &DATA[..5]
+                        )
+                    } else if start > 0 && self.honor_ranges && if_range == etag {
+                        format!(
+                            "HTTP/1.1 206 Partial Content\r\n{}Content-Range: bytes {}-9/10\r\n\
+                             Content-Length: {}\r\n\r\n{}",
+                            head,
+                            start,
+                            DATA.len() - start,
+                            &DATA[start..]
+                        )
+                    } else {
+                        format!(
+                            "HTTP/1.1 200 OK\r\n{}Content-Length: 10\r\n\r\n{}",
+                            head, DATA
+                        )
+                    }
+                };
+
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+
+        (url, ranges)
+    }
+}
+
+// downloads from `server` twice, the first attempt being cut off, and returns the bytes
+// downloaded by the second
+fn download_twice(server: Server, name: &str) -> u64 {
+    let (url, _) = server.start();
+    let dir: PathBuf = std::env::temp_dir().join(format!("datasets_http_resume_{}", name));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let downloader = Downloader::new().retry(RetryPolicy::never());
+
+    assert!(downloader.download(&url, &dir, false).is_err());
+    assert_eq!(
+        std::fs::read_to_string(dir.join("data.txt")).unwrap(),
+        "01234"
+    );
+
+    let downloaded = downloader.download(&url, &dir, false).unwrap();
+    assert_eq!(std::fs::read_to_string(dir.join("data.txt")).unwrap(), DATA);
+
+    downloaded
+}
+
+#[test]
+fn resumes_with_partial_content() {
+    let server = Server {
+        accept_ranges: true,
+        honor_ranges: true,
+        change_etag: false,
+    };
+
+    assert_eq!(download_twice(server, "partial_content"), 5);
+}
+
+#[test]
+fn restarts_when_the_range_is_ignored() {
+    let server = Server {
+        accept_ranges: true,
+        honor_ranges: false,
+        change_etag: false,
+    };
+
+    assert_eq!(download_twice(server, "range_ignored"), 10);
+}
+
+#[test]
+fn restarts_when_the_file_changed() {
+    let server = Server {
+        accept_ranges: true,
+        honor_ranges: true,
+        change_etag: true,
+    };
+
+    assert_eq!(download_twice(server, "file_changed"), 10);
+}
+
+#[test]
+fn restarts_when_ranges_are_not_supported() {
+    let server = Server {
+        accept_ranges: false,
+        honor_ranges: true,
+        change_etag: false,
+    };
+
+    assert_eq!(download_twice(server, "ranges_unsupported"), 10);
+}
+
+#[test]
+fn retries_resume_after_the_connection_is_dropped() {
+    let server = Server {
+        accept_ranges: true,
+        honor_ranges: true,
+        change_etag: false,
+    };
+
+    let (url, ranges) = server.start();
+    let dir = std::env::temp_dir().join("datasets_http_resume_retry");
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let retry = RetryPolicy::new(2)
+        .initial_backoff(Duration::from_secs(0))
+        .jitter(false);
+    let downloader = Downloader::new().retry(retry);
+
+    // the first response is cut off, and the retry within the same call resumes it
+    downloader.download(&url, &dir, false).unwrap();
+    assert_eq!(std::fs::read_to_string(dir.join("data.txt")).unwrap(), DATA);
+
+    let ranges = ranges.lock().unwrap();
+    assert_eq!(*ranges, vec![None, Some("bytes=5-".to_string())]);
+}
@@ -0,0 +1,178 @@
+//! Loading mnist from small fixture files, instead of the published dataset.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use flate2::{Compression, GzBuilder};
+
+use datasets::image::mnist;
+use datasets::utils::{DirectoryTransport, Downloader};
+use datasets::{Cacheable, Checkpoint, Dataset};
+
+const IMAGE_SIZE: usize = 28 * 28;
+
+// the idx files of `n` images, where image `i` is filled with `i` and labeled `i % 10`
+fn idx_files(n: usize) -> (Vec<u8>, Vec<u8>) {
+    let mut images = vec![0, 0, 8, 3];
+    images.extend_from_slice(&(n as u32).to_be_bytes());
+    images.extend_from_slice(&28u32.to_be_bytes());
+    images.extend_from_slice(&28u32.to_be_bytes());
+
+    let mut labels = vec![0, 0, 8, 1];
+    labels.extend_from_slice(&(n as u32).to_be_bytes());
+
+    for i in 0..n {
+        images.extend(vec![i as u8; IMAGE_SIZE]);
+        labels.push((i % 10) as u8);
+    }
+
+    (images, labels)
+}
+
+// a download directory with already extracted train and test files, so nothing is downloaded
+fn extracted_fixture(name: &str, train: usize, test: usize) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("datasets_mnist_{}", name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    for (prefix, n) in &[("train", train), ("t10k", test)] {
+        let (images, labels) = idx_files(*n);
+        fs::write(dir.join(format!("{}-images.idx3-ubyte", prefix)), images).unwrap();
+        fs::write(dir.join(format!("{}-labels.idx1-ubyte", prefix)), labels).unwrap();
+
+        for kind in &["images-idx3", "labels-idx1"] {
+            let marker = format!("{}-{}-ubyte.gz.completed", prefix, kind);
+            fs::write(dir.join(marker), "").unwrap();
+        }
+    }
+
+    dir
+}
+
+// a mirror serving the gzipped train and test files, named in their headers like the published ones
+fn gzipped_fixture(name: &str, train: usize, test: usize) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("datasets_mnist_{}_mirror", name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+
+    for (prefix, n) in &[("train", train), ("t10k", test)] {
+        let (images, labels) = idx_files(*n);
+        for (kind, data) in &[("images.idx3", images), ("labels.idx1", labels)] {
+            let name = format!("{}-{}-ubyte", prefix, kind);
+            let file = File::create(dir.join(format!("{}.gz", name.replace('.', "-")))).unwrap();
+
+            let mut gz = GzBuilder::new()
+                .filename(name)
+                .write(file, Compression::default());
+            gz.write_all(data).unwrap();
+            gz.finish().unwrap();
+        }
+    }
+
+    dir
+}
+
+fn labels<D>(dataset: D) -> Vec<u8>
+where
+    D: Iterator<Item = (Vec<u8>, u8)>,
+{
+    dataset.map(|(_, label)| label).collect()
+}
+
+fn remove_files(dir: &Path) {
+    for name in &["train-images.idx3-ubyte", "train-labels.idx1-ubyte"] {
+        fs::remove_file(dir.join(name)).unwrap();
+    }
+}
+
+#[test]
+fn load_reads_the_sizes_from_the_headers() {
+    let dir = extracted_fixture("sizes", 12, 3);
+    let (train, test) = mnist::load(&dir).unwrap();
+
+    assert_eq!(train.len(), 12);
+    assert_eq!(test.len(), 3);
+    assert_eq!(labels(test), vec![0, 1, 2]);
+}
+
+#[test]
+fn load_with_downloads_and_extracts_the_files() {
+    let mirror = gzipped_fixture("download", 5, 2);
+    let dir = std::env::temp_dir().join("datasets_mnist_download");
+    let _ = fs::remove_dir_all(&dir);
+
+    let downloader = Downloader::new()
+        .transport(DirectoryTransport::new(&mirror))
+        .verify_checksums(false);
+    let (train, test) = mnist::load_with(&dir, &downloader).unwrap();
+
+    assert_eq!(labels(train), vec![0, 1, 2, 3, 4]);
+    let images: Vec<_> = test.map(|(image, _)| image).collect();
+    assert_eq!(images, vec![vec![0; IMAGE_SIZE], vec![1; IMAGE_SIZE]]);
+}
+
+#[test]
+fn load_with_checks_the_published_checksums() {
+    let mirror = gzipped_fixture("checksums", 5, 2);
+    let dir = std::env::temp_dir().join("datasets_mnist_checksums");
+    let _ = fs::remove_dir_all(&dir);
+
+    let downloader = Downloader::new().transport(DirectoryTransport::new(&mirror));
+    assert!(mnist::load_with(&dir, &downloader).is_err());
+    assert!(!dir.join("train-images-idx3-ubyte.gz").exists());
+}
+
+#[test]
+#[should_panic(expected = "mnist: unable to reopen the files")]
+fn repeat_panics_when_the_files_cannot_be_reopened() {
+    let dir = extracted_fixture("reopen", 4, 1);
+    let (train, _) = mnist::load(&dir).unwrap();
+
+    let mut repeated = train.repeat(2);
+    assert_eq!(labels(repeated.by_ref().take(4)), vec![0, 1, 2, 3]);
+
+    // the files of the first epoch stay open, but the next one cannot open them again
+    remove_files(&dir);
+    repeated.next();
+}
+
+#[test]
+fn restore_resumes_from_the_saved_record() {
+    let dir = extracted_fixture("restore", 10, 1);
+    let normalize = |(image, label): (Vec<u8>, u8)| (image[0] as f32 / 255.0, label);
+
+    let (train, _) = mnist::load(&dir).unwrap();
+    let mut data = train
+        .checkpointable_map(normalize)
+        .shuffle(4, 0)
+        .batch(3, false);
+    data.next();
+
+    let mut state = Vec::new();
+    data.save_state(&mut state).unwrap();
+
+    let (train, _) = mnist::load(&dir).unwrap();
+    let mut restored = train
+        .checkpointable_map(normalize)
+        .shuffle(4, 0)
+        .batch(3, false);
+    restored.restore_state(&mut &state[..]).unwrap();
+
+    assert_eq!(restored.collect::<Vec<_>>(), data.collect::<Vec<_>>());
+}
+
+#[test]
+fn restore_rejects_a_position_past_the_end() {
+    let dir = extracted_fixture("restore_past_end", 10, 1);
+    let (mut train, _) = mnist::load(&dir).unwrap();
+
+    let mut state = Vec::new();
+    11usize.write_to(&mut state).unwrap();
+    assert!(train.restore_state(&mut &state[..]).is_err());
+
+    let mut state = Vec::new();
+    7usize.write_to(&mut state).unwrap();
+    train.restore_state(&mut &state[..]).unwrap();
+    assert_eq!(labels(train), vec![7, 8, 9]);
+}
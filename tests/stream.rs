@@ -0,0 +1,72 @@
+//! Streams driven by an executor, without a background thread polling them.
+#![cfg(feature = "stream")]
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use datasets::stream::DatasetStream;
+use datasets::Dataset;
+use futures::executor::block_on;
+use futures::{Stream, StreamExt};
+
+// a stream that is pending before every element, waking its task, like a stream waiting on
+// a reactor; it must be polled from the consumer's task to make progress
+struct Pending {
+    next: usize,
+    end: usize,
+    ready: bool,
+}
+
+impl Stream for Pending {
+    type Item = usize;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<usize>> {
+        if self.next == self.end {
+            return Poll::Ready(None);
+        }
+
+        if !self.ready {
+            self.ready = true;
+            cx.waker().wake_by_ref();
+            return Poll::Pending;
+        }
+
+        self.ready = false;
+        self.next += 1;
+        Poll::Ready(Some(self.next - 1))
+    }
+}
+
+#[test]
+fn prefetch_polls_pending_streams_from_the_consumer() {
+    let stream = Pending {
+        next: 0,
+        end: 6,
+        ready: false,
+    };
+
+    let v: Vec<usize> = block_on(stream.prefetch(2).collect());
+    assert_eq!(v, vec![0, 1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn prefetch_of_zero_returns_every_element() {
+    let v: Vec<usize> = block_on(futures::stream::iter(0..4).prefetch(0).collect());
+    assert_eq!(v, vec![0, 1, 2, 3]);
+}
+
+#[test]
+#[should_panic(expected = "invalid element")]
+fn into_stream_resumes_panics() {
+    let data = (0..8).map(|x| if x == 4 { panic!("invalid element") } else { x });
+    let _: Vec<usize> = block_on(data.into_stream(2).collect());
+}
+
+#[test]
+fn into_stream_stops_when_dropped() {
+    let mut stream = (0..).into_stream(2);
+    assert_eq!(block_on(stream.next()), Some(0));
+
+    // the worker is blocked on the full channel, and is joined when the receiver is dropped
+    drop(stream);
+}